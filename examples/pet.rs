@@ -2,11 +2,18 @@
 #![feature(generic_const_exprs)]
 #![feature(adt_const_params)]
 
-/// Matrix type.
-pub struct Matrix4x4([f32; 16]);
+magma::block_type! {
+    /// Matrix type.
+    pub struct Matrix4x4 : std140 {
+        /// Columns of the matrix.
+        pub columns: [[f32; 4]; 4]
+    }
+}
 
-pub struct SpriteData {
-    // ...
+magma::block_type! {
+    pub struct SpriteData : std140 {
+        // ...
+    }
 }
 
 /// Untyped pipeline layouts.
@@ -4,12 +4,14 @@ use ash::{
 };
 use std::{
 	collections::HashSet,
-	marker::PhantomData
+	marker::PhantomData,
+	sync::Weak
 };
 use crate::{
 	resource,
 	OomError,
 	DeviceOwned,
+	mem::HostWrites,
 	sync::{
 		future::Futures,
 		task
@@ -76,6 +78,7 @@ pub trait Buffer: Sized + DeviceOwned {
 
 		let mut recorder = Recorder {
 			buffer: self,
+			host_writes: Vec::new(),
 			lft: PhantomData
 		};
 
@@ -87,6 +90,7 @@ pub trait Buffer: Sized + DeviceOwned {
 
 		Ok(Recorded {
 			buffer: recorder.buffer,
+			host_writes: recorder.host_writes,
 			resources
 		})
 	}
@@ -131,6 +135,14 @@ impl<'a, B: Buffer> Buffer for &'a B {
 pub unsafe trait RecordedBuffer: task::Payload {
 	fn handle(&self) -> vk::CommandBuffer;
 
+	/// Flushes the host writes to the non-coherent memory read by the command buffer.
+	///
+	/// This is called right before the command buffer is submitted.
+	#[inline]
+	fn flush_host_writes(&self) -> Result<(), OomError> {
+		Ok(())
+	}
+
 	// /// Validate the references owned by the command buffer against the given past.
 	// fn check_borrow_rules<P: Futures>(&self, past: &P);
 }
@@ -141,6 +153,11 @@ unsafe impl<'a, B: RecordedBuffer> RecordedBuffer for &'a B {
 		(*self).handle()
 	}
 
+	#[inline]
+	fn flush_host_writes(&self) -> Result<(), OomError> {
+		(*self).flush_host_writes()
+	}
+
 	// #[inline]
 	// fn check_borrow_rules<P: Futures>(&self, past: &P) {
 	// 	(*self).check_borrow_rules(past)
@@ -149,6 +166,10 @@ unsafe impl<'a, B: RecordedBuffer> RecordedBuffer for &'a B {
 
 pub struct Recorded<B: Buffer, R> {
 	buffer: B,
+
+	/// Host writes to flush before each submission.
+	host_writes: Vec<Weak<HostWrites>>,
+
 	resources: R
 }
 
@@ -168,6 +189,18 @@ unsafe impl<B: Buffer, R> RecordedBuffer for Recorded<B, R> {
 		self.buffer.handle()
 	}
 
+	fn flush_host_writes(&self) -> Result<(), OomError> {
+		// Slots dropped since recording have nothing left to flush.
+		let ranges: Vec<_> = self.host_writes.iter().filter_map(|writes| writes.upgrade()?.take()).collect();
+		if !ranges.is_empty() {
+			unsafe {
+				self.buffer.device().handle().flush_mapped_memory_ranges(&ranges)?
+			}
+		}
+
+		Ok(())
+	}
+
 	// #[inline]
 	// fn check_borrow_rules<P: Futures>(&self, past: &P) {
 	// 	for resource in &self.resources {
@@ -3,7 +3,10 @@ use ash::{
 	version::DeviceV1_0
 };
 use std::{
-	sync::Arc,
+	sync::{
+		Arc,
+		Weak
+	},
 	collections::HashSet,
 	marker::PhantomData
 };
@@ -30,6 +33,10 @@ use super::{
 
 pub struct Recorder<'a, B: Buffer> {
 	pub(crate) buffer: B,
+
+	/// Host writes to flush before each submission.
+	pub(crate) host_writes: Vec<Weak<mem::HostWrites>>,
+
 	pub(crate) lft: PhantomData<&'a ()>
 }
 
@@ -66,11 +73,49 @@ impl<'a, B: Buffer> Recorder<'a, B> {
 		}
 	}
 
-	pub fn copy_buffer<S: 'a + Send + mem::buffer::sub::Read, D: 'a + Send + mem::buffer::sub::Write>(&mut self, src: S, dst: D, regions: &[BufferCopy]) {
+	/// Flushes the host writes to the given buffer each time the command buffer is submitted.
+	///
+	/// Commands of this recorder reading a buffer already do this,
+	/// but buffers read through descriptor sets or raw commands must be declared here.
+	pub fn read_host_writes<R: mem::buffer::sub::Read>(&mut self, buffer: &R) {
+		if let Some(writes) = buffer.host_writes() {
+			if !self.host_writes.iter().any(|w| std::ptr::eq(w.as_ptr(), Arc::as_ptr(&writes))) {
+				self.host_writes.push(Arc::downgrade(&writes))
+			}
+		}
+	}
+
+	/// Copies regions of a buffer into another.
+	///
+	/// The region offsets are relative to the source and destination sub-buffers.
+	/// The source must have the `TransferSource` usage,
+	/// and the destination the `TransferDestination` usage (see `buffer::usage::Set`).
+	pub fn copy_buffer<S, D>(&mut self, src: S, dst: D, regions: &[BufferCopy])
+	where
+		S: 'a + Send + mem::buffer::sub::Read,
+		S::Usage: mem::buffer::usage::TransferSource,
+		D: 'a + Send + mem::buffer::sub::Write,
+		D::Usage: mem::buffer::usage::TransferDestination
+	{
+		let (src_offset, dst_offset) = (src.byte_offset(), dst.byte_offset());
+		debug_assert!(regions.iter().all(|r| r.src_offset + r.size <= src.byte_len() && r.dst_offset + r.size <= dst.byte_len()));
+
 		unsafe {
-			self.buffer.device().handle().cmd_copy_buffer(self.buffer.handle(), src.handle(), dst.handle(), regions)
+			if src_offset == 0 && dst_offset == 0 {
+				self.buffer.device().handle().cmd_copy_buffer(self.buffer.handle(), src.handle(), dst.handle(), regions)
+			} else {
+				let regions: Vec<_> = regions.iter().map(|r| BufferCopy {
+					src_offset: src_offset + r.src_offset,
+					dst_offset: dst_offset + r.dst_offset,
+					size: r.size
+				}).collect();
+
+				self.buffer.device().handle().cmd_copy_buffer(self.buffer.handle(), src.handle(), dst.handle(), &regions)
+			}
 		}
 
+		self.read_host_writes(&src);
+
 		// self.resources.insert(src.into());
 		// self.resources.insert(dst.into());
 	}
@@ -1,6 +1,7 @@
 use ash::vk;
 
 pub mod ty;
+pub mod block;
 pub mod set;
 pub mod pool;
 // pub mod update;
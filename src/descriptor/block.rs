@@ -0,0 +1,322 @@
+//! Uniform and storage buffer block layouts.
+//!
+//! GLSL uniform and storage blocks follow the std140 or std430 layout rules,
+//! which differ from the C representation used by Rust:
+//! for instance a `vec3` is aligned on 16 bytes.
+//! The [`block_type!`] macro declares a structure following one of these layouts,
+//! inserting the required padding between its fields.
+
+/// Block layout rules.
+pub trait Layout {
+	/// Minimum alignment of structures and arrays of structures.
+	const MIN_STRUCT_ALIGN: usize;
+}
+
+/// std140 layout, required for uniform blocks.
+pub struct Std140;
+
+impl Layout for Std140 {
+	/// Structures are aligned on a `vec4`.
+	const MIN_STRUCT_ALIGN: usize = 16;
+}
+
+/// std430 layout, available for storage blocks.
+pub struct Std430;
+
+impl Layout for Std430 {
+	const MIN_STRUCT_ALIGN: usize = 1;
+}
+
+/// Type that can be the field of a block with layout `L`.
+///
+/// ## Safety
+///
+/// `SIZE` must be the size of the type (`std::mem::size_of::<Self>()`),
+/// equal to its size in the layout `L`,
+/// and the alignment of the type must divide `ALIGN`.
+pub unsafe trait Field<L: Layout>: Copy {
+	/// Base alignment of the type in the layout.
+	const ALIGN: usize;
+
+	/// Size of the type in the layout.
+	const SIZE: usize;
+}
+
+macro_rules! fields {
+	($($ty:ty : $align:literal, $size:literal),*) => {
+		$(
+			unsafe impl<L: Layout> Field<L> for $ty {
+				const ALIGN: usize = $align;
+				const SIZE: usize = $size;
+			}
+		)*
+	};
+}
+
+fields! {
+	f32: 4, 4,
+	i32: 4, 4,
+	u32: 4, 4,
+	f64: 8, 8,
+	[f32; 2]: 8, 8,
+	[i32; 2]: 8, 8,
+	[u32; 2]: 8, 8,
+	[f64; 2]: 16, 16,
+	[f32; 3]: 16, 12,
+	[i32; 3]: 16, 12,
+	[u32; 3]: 16, 12,
+	[f64; 3]: 32, 24,
+	[f32; 4]: 16, 16,
+	[i32; 4]: 16, 16,
+	[u32; 4]: 16, 16,
+	[f64; 4]: 32, 32,
+
+	// Column-major matrices whose column stride does not depend on the layout.
+	[[f32; 4]; 2]: 16, 32,
+	[[f32; 4]; 3]: 16, 48,
+	[[f32; 4]; 4]: 16, 64,
+	[[f64; 2]; 2]: 16, 32,
+	[[f64; 2]; 3]: 16, 48,
+	[[f64; 2]; 4]: 16, 64,
+	[[f64; 4]; 2]: 32, 64,
+	[[f64; 4]; 3]: 32, 96,
+	[[f64; 4]; 4]: 32, 128
+}
+
+// In std430, `vec2` matrix columns are not padded to a `vec4`.
+unsafe impl Field<Std430> for [[f32; 2]; 2] {
+	const ALIGN: usize = 8;
+	const SIZE: usize = 16;
+}
+
+unsafe impl Field<Std430> for [[f32; 2]; 3] {
+	const ALIGN: usize = 8;
+	const SIZE: usize = 24;
+}
+
+unsafe impl Field<Std430> for [[f32; 2]; 4] {
+	const ALIGN: usize = 8;
+	const SIZE: usize = 32;
+}
+
+/// Structure following a block layout.
+///
+/// This trait can be easily and safely implemented
+/// using the [`block_type!`] macro.
+///
+/// ## Safety
+///
+/// The type must follow the `Layout` rules,
+/// and its size must be `SIZE`, a multiple of `ALIGN`.
+pub unsafe trait Block: Copy {
+	/// Layout of the block.
+	type Layout: Layout;
+
+	/// Base alignment of the structure in the layout.
+	const ALIGN: usize;
+
+	/// Size of the structure in the layout.
+	const SIZE: usize;
+}
+
+/// A block is a valid field of a block with the same layout.
+unsafe impl<L: Layout, T: Block<Layout = L>> Field<L> for T {
+	const ALIGN: usize = T::ALIGN;
+	const SIZE: usize = T::SIZE;
+}
+
+/// Since the size of a block is a multiple of its alignment,
+/// arrays of blocks have the layout array stride.
+unsafe impl<L: Layout, T: Block<Layout = L>, const N: usize> Field<L> for [T; N] {
+	const ALIGN: usize = T::ALIGN;
+	const SIZE: usize = T::SIZE * N;
+}
+
+/// Data that can be bound to a uniform buffer descriptor.
+///
+/// Implemented by std140 blocks and arrays of std140 blocks (one block per descriptor).
+pub unsafe trait UniformBlock {}
+
+unsafe impl<T: Block<Layout = Std140>> UniformBlock for T {}
+
+unsafe impl<T: Block<Layout = Std140>, const N: usize> UniformBlock for [T; N] {}
+
+/// Data that can be bound to a storage buffer descriptor.
+///
+/// Implemented by std140 and std430 blocks and arrays of such blocks (one block per descriptor).
+pub unsafe trait StorageBlock {}
+
+unsafe impl<T: Block> StorageBlock for T {}
+
+unsafe impl<T: Block, const N: usize> StorageBlock for [T; N] {}
+
+#[doc(hidden)]
+pub const fn align_up(offset: usize, align: usize) -> usize {
+	match offset % align {
+		0 => offset,
+		trail => offset + align - trail
+	}
+}
+
+#[doc(hidden)]
+pub const fn max(a: usize, b: usize) -> usize {
+	if a > b {
+		a
+	} else {
+		b
+	}
+}
+
+/// Create a block type following the std140 or std430 layout.
+///
+/// The produced type uses the C representation (`#[repr(C)]`),
+/// with hidden padding fields inserted so that each field is placed
+/// at its offset in the layout.
+/// Because of these hidden fields, the type is built using the generated `new` function,
+/// taking the value of each field in order.
+/// The type derives `Clone` and `Copy`.
+///
+/// The type of each field must implement `Field` for the layout:
+/// scalars (`f32`, `i32`, `u32`, `f64`), vectors (arrays of 2 to 4 scalars),
+/// column-major matrices, and other block types of the same layout, or arrays of them.
+/// Other fields are rejected at compile time.
+/// Blocks are limited to 32 fields.
+///
+/// ## Example
+///
+/// ```
+/// magma::block_type! {
+///   /// Point light.
+///   pub struct Light : std140 {
+///     /// Position of the light, aligned on 16 bytes.
+///     pub position: [f32; 3],
+///
+///     /// Packed right after the position.
+///     pub intensity: f32,
+///
+///     pub color: [f32; 3]
+///   }
+/// }
+///
+/// let light = Light::new([0.0, 1.0, 0.0], 1.0, [1.0, 1.0, 1.0]);
+/// ```
+#[macro_export]
+macro_rules! block_type {
+	{
+		$(#[$meta:meta])*
+		$vis:vis struct $id:ident : $layout:ident {
+			$(
+				$(#[$field_meta:meta])*
+				$field_vis:vis
+				$field_id:ident : $field_ty:ty
+			),* $(,)?
+		}
+	} => {
+		$crate::block_type!(
+			@fields
+			[$crate::block_type!(@layout $layout) ; $(#[$meta])* $vis struct $id]
+			[
+				__pad0 __pad1 __pad2 __pad3 __pad4 __pad5 __pad6 __pad7
+				__pad8 __pad9 __pad10 __pad11 __pad12 __pad13 __pad14 __pad15
+				__pad16 __pad17 __pad18 __pad19 __pad20 __pad21 __pad22 __pad23
+				__pad24 __pad25 __pad26 __pad27 __pad28 __pad29 __pad30 __pad31
+			]
+			[0]
+			[<$crate::block_type!(@layout $layout) as $crate::descriptor::block::Layout>::MIN_STRUCT_ALIGN]
+			[]
+			[]
+			[]
+			[$(($(#[$field_meta])* $field_vis $field_id : $field_ty))*]
+		);
+	};
+	(@layout std140) => { $crate::descriptor::block::Std140 };
+	(@layout std430) => { $crate::descriptor::block::Std430 };
+	(@layout $other:ident) => {
+		compile_error!(concat!("unsupported block layout `", stringify!($other), "` (expected `std140` or `std430`)"))
+	};
+	(
+		@fields
+		[$layout:ty ; $($header:tt)*]
+		[$pad:ident $($pads:ident)*]
+		[$end:expr]
+		[$align:expr]
+		[$($fields:tt)*]
+		[$($args:tt)*]
+		[$($inits:tt)*]
+		[($(#[$field_meta:meta])* $field_vis:vis $field_id:ident : $field_ty:ty) $($rest:tt)*]
+	) => {
+		$crate::block_type!(
+			@fields
+			[$layout ; $($header)*]
+			[$($pads)*]
+			[$crate::descriptor::block::align_up($end, <$field_ty as $crate::descriptor::block::Field<$layout>>::ALIGN) + <$field_ty as $crate::descriptor::block::Field<$layout>>::SIZE]
+			[$crate::descriptor::block::max($align, <$field_ty as $crate::descriptor::block::Field<$layout>>::ALIGN)]
+			[
+				$($fields)*
+				#[allow(dead_code)]
+				$pad: [u8; $crate::descriptor::block::align_up($end, <$field_ty as $crate::descriptor::block::Field<$layout>>::ALIGN) - $end],
+				$(#[$field_meta])*
+				$field_vis $field_id : $field_ty,
+			]
+			[$($args)* $field_id : $field_ty,]
+			[
+				$($inits)*
+				$pad: [0u8; $crate::descriptor::block::align_up($end, <$field_ty as $crate::descriptor::block::Field<$layout>>::ALIGN) - $end],
+				$field_id,
+			]
+			[$($rest)*]
+		);
+	};
+	(
+		@fields
+		[$layout:ty ; $($header:tt)*]
+		[]
+		[$end:expr]
+		[$align:expr]
+		[$($fields:tt)*]
+		[$($args:tt)*]
+		[$($inits:tt)*]
+		[$($rest:tt)+]
+	) => {
+		compile_error!("too many fields in block type (at most 32)");
+	};
+	(
+		@fields
+		[$layout:ty ; $(#[$meta:meta])* $vis:vis struct $id:ident]
+		[$($pads:ident)*]
+		[$end:expr]
+		[$align:expr]
+		[$($fields:tt)*]
+		[$($args:tt)*]
+		[$($inits:tt)*]
+		[]
+	) => {
+		$(#[$meta])*
+		#[repr(C)]
+		#[derive(Clone, Copy)]
+		$vis struct $id {
+			$($fields)*
+			#[allow(dead_code)]
+			__pad_end: [u8; $crate::descriptor::block::align_up($end, $align) - $end]
+		}
+
+		impl $id {
+			#[allow(clippy::too_many_arguments)]
+			pub const fn new($($args)*) -> Self {
+				Self {
+					$($inits)*
+					__pad_end: [0u8; $crate::descriptor::block::align_up($end, $align) - $end]
+				}
+			}
+		}
+
+		unsafe impl $crate::descriptor::block::Block for $id {
+			type Layout = $layout;
+			const ALIGN: usize = $align;
+			const SIZE: usize = $crate::descriptor::block::align_up($end, $align);
+		}
+
+		$crate::static_assertions::const_assert_eq!($crate::std::mem::size_of::<$id>(), <$id as $crate::descriptor::block::Block>::SIZE);
+		$crate::static_assertions::const_assert_eq!(<$id as $crate::descriptor::block::Block>::ALIGN % $crate::std::mem::align_of::<$id>(), 0);
+	};
+}
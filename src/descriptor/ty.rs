@@ -1,6 +1,10 @@
 use std::marker::PhantomData;
 use ash::vk;
 use crate::pipeline::shader;
+use super::block::{
+	UniformBlock,
+	StorageBlock
+};
 
 /// Descriptor type.
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -64,6 +68,10 @@ pub unsafe trait ArrayLen<const L: u32>: Array {}
 
 unsafe impl<T: Array> ArrayLen<{T::COUNT}> for T {}
 
+/// Uniform buffer descriptor.
+///
+/// The data type `T` must be a std140 block (see the `block_type!` macro),
+/// or an array of std140 blocks for an array of descriptors.
 pub struct UniformBuffer<T>(PhantomData<T>);
 
 // unsafe impl<B, T> crate::descriptor::set::layout::WellTyped<B> for UniformBuffer<T>
@@ -71,11 +79,22 @@ pub struct UniformBuffer<T>(PhantomData<T>);
 // 	// ...
 // {}
 
-impl<T: Array> DataType for UniformBuffer<T> {
+impl<T: Array + UniformBlock> DataType for UniformBuffer<T> {
 	const DESCRIPTOR_TYPE: Type = Type::UniformBuffer;
 	const COUNT: u32 = T::COUNT;
 }
 
+/// Storage buffer descriptor.
+///
+/// The data type `T` must be a std140 or std430 block (see the `block_type!` macro),
+/// or an array of such blocks for an array of descriptors.
+pub struct StorageBuffer<T>(PhantomData<T>);
+
+impl<T: Array + StorageBlock> DataType for StorageBuffer<T> {
+	const DESCRIPTOR_TYPE: Type = Type::StorageBuffer;
+	const COUNT: u32 = T::COUNT;
+}
+
 pub unsafe trait WellTyped<const DESCRIPTOR_TYPE: Type, const COUNT: u32> {}
 
 unsafe impl<T: DataType> WellTyped<{T::DESCRIPTOR_TYPE}, {T::COUNT}> for T {}
@@ -17,4 +17,11 @@ extensions! {
 	ext_debug_utils: ExtDebugUtils => b"VK_EXT_debug_utils\0",
 	khr_multiview: KhrMultiview => b"VK_KHR_multiview\0",
 	ext_full_screen_exclusive: ExtFullScreenExclusive => b"VK_EXT_full_screen_exclusive\0",
+	ext_memory_budget: ExtMemoryBudget => b"VK_EXT_memory_budget\0",
+	khr_external_memory: KhrExternalMemory => b"VK_KHR_external_memory\0",
+	khr_external_memory_fd: KhrExternalMemoryFd => b"VK_KHR_external_memory_fd\0",
+	ext_external_memory_dma_buf: ExtExternalMemoryDmaBuf => b"VK_EXT_external_memory_dma_buf\0",
+	khr_buffer_device_address: KhrBufferDeviceAddress => b"VK_KHR_buffer_device_address\0",
+	khr_timeline_semaphore: KhrTimelineSemaphore => b"VK_KHR_timeline_semaphore\0",
+	khr_device_group: KhrDeviceGroup => b"VK_KHR_device_group\0",
 }
@@ -0,0 +1,233 @@
+use std::fmt;
+use ash::vk;
+use crate::{
+	OomError,
+	instance
+};
+use super::MissingExtensionError;
+
+/// External memory handle type.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum HandleType {
+	/// POSIX file descriptor only meaningful to Vulkan devices using the same driver.
+	///
+	/// Requires the `VK_KHR_external_memory_fd` extension.
+	OpaqueFd,
+
+	/// Linux dma-buf file descriptor.
+	///
+	/// Requires the `VK_KHR_external_memory_fd` and `VK_EXT_external_memory_dma_buf` extensions.
+	DmaBuf
+}
+
+impl HandleType {
+	#[inline]
+	pub(crate) fn into_vulkan(self) -> vk::ExternalMemoryHandleTypeFlags {
+		match self {
+			HandleType::OpaqueFd => vk::ExternalMemoryHandleTypeFlags::EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_FD,
+			HandleType::DmaBuf => vk::ExternalMemoryHandleTypeFlags::EXTERNAL_MEMORY_HANDLE_TYPE_DMA_BUF
+		}
+	}
+}
+
+/// Set of external memory handle types.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct HandleTypes {
+	pub opaque_fd: bool,
+	pub dma_buf: bool
+}
+
+impl HandleTypes {
+	#[inline]
+	pub fn none() -> Self {
+		Self::default()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		!self.opaque_fd && !self.dma_buf
+	}
+
+	#[inline]
+	pub fn contains(&self, handle_type: HandleType) -> bool {
+		match handle_type {
+			HandleType::OpaqueFd => self.opaque_fd,
+			HandleType::DmaBuf => self.dma_buf
+		}
+	}
+
+	pub(crate) fn from_vulkan(flags: vk::ExternalMemoryHandleTypeFlags) -> Self {
+		Self {
+			opaque_fd: flags.contains(HandleType::OpaqueFd.into_vulkan()),
+			dma_buf: flags.contains(HandleType::DmaBuf.into_vulkan())
+		}
+	}
+
+	pub(crate) fn into_vulkan(self) -> vk::ExternalMemoryHandleTypeFlags {
+		let mut flags = vk::ExternalMemoryHandleTypeFlags::empty();
+
+		if self.opaque_fd {
+			flags |= HandleType::OpaqueFd.into_vulkan()
+		}
+
+		if self.dma_buf {
+			flags |= HandleType::DmaBuf.into_vulkan()
+		}
+
+		flags
+	}
+}
+
+impl From<HandleType> for HandleTypes {
+	fn from(handle_type: HandleType) -> Self {
+		let mut handle_types = Self::none();
+
+		match handle_type {
+			HandleType::OpaqueFd => handle_types.opaque_fd = true,
+			HandleType::DmaBuf => handle_types.dma_buf = true
+		}
+
+		handle_types
+	}
+}
+
+/// External memory capabilities of a resource for a given handle type.
+///
+/// Returned by `PhysicalDevice::external_buffer_properties` and `PhysicalDevice::external_image_properties`.
+#[derive(Clone, Copy)]
+pub struct Properties {
+	vulkan: vk::ExternalMemoryProperties
+}
+
+impl Properties {
+	#[inline]
+	pub(crate) fn new(vulkan: vk::ExternalMemoryProperties) -> Self {
+		Self {
+			vulkan
+		}
+	}
+
+	/// Memory of the resource can be exported with this handle type.
+	#[inline]
+	pub fn is_exportable(&self) -> bool {
+		self.vulkan.external_memory_features.contains(vk::ExternalMemoryFeatureFlags::EXTERNAL_MEMORY_FEATURE_EXPORTABLE)
+	}
+
+	/// Memory of the resource can be imported from this handle type.
+	#[inline]
+	pub fn is_importable(&self) -> bool {
+		self.vulkan.external_memory_features.contains(vk::ExternalMemoryFeatureFlags::EXTERNAL_MEMORY_FEATURE_IMPORTABLE)
+	}
+
+	/// Exported or imported memory must be dedicated to the resource.
+	#[inline]
+	pub fn is_dedicated_only(&self) -> bool {
+		self.vulkan.external_memory_features.contains(vk::ExternalMemoryFeatureFlags::EXTERNAL_MEMORY_FEATURE_DEDICATED_ONLY)
+	}
+
+	/// Handle types that can be exported from memory imported with this handle type.
+	#[inline]
+	pub fn export_from_imported_handle_types(&self) -> HandleTypes {
+		HandleTypes::from_vulkan(self.vulkan.export_from_imported_handle_types)
+	}
+
+	/// Handle types that can be specified at the same time as this one when creating exportable memory.
+	#[inline]
+	pub fn compatible_handle_types(&self) -> HandleTypes {
+		HandleTypes::from_vulkan(self.vulkan.compatible_handle_types)
+	}
+}
+
+impl fmt::Debug for Properties {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Properties")
+			.field("exportable", &self.is_exportable())
+			.field("importable", &self.is_importable())
+			.field("dedicated_only", &self.is_dedicated_only())
+			.field("export_from_imported_handle_types", &self.export_from_imported_handle_types())
+			.field("compatible_handle_types", &self.compatible_handle_types())
+			.finish()
+	}
+}
+
+/// External memory properties query error.
+#[derive(Debug)]
+pub enum QueryError {
+	MissingInstanceExtension(instance::MissingExtensionError),
+	OutOfMemory(OomError)
+}
+
+impl fmt::Display for QueryError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::MissingInstanceExtension(e) => write!(f, "missing instance extension `{}`", e.0),
+			Self::OutOfMemory(e) => e.fmt(f)
+		}
+	}
+}
+
+impl std::error::Error for QueryError {
+	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+		match self {
+			Self::OutOfMemory(e) => Some(e),
+			_ => None
+		}
+	}
+}
+
+impl From<instance::MissingExtensionError> for QueryError {
+	fn from(e: instance::MissingExtensionError) -> Self {
+		Self::MissingInstanceExtension(e)
+	}
+}
+
+/// Memory export error.
+#[derive(Debug)]
+pub enum ExportError {
+	MissingExtension(MissingExtensionError),
+	NotExportable(HandleType),
+	TooManyObjects,
+	OutOfMemory(OomError),
+
+	/// Other error returned by the driver.
+	Unexpected(vk::Result)
+}
+
+impl fmt::Display for ExportError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::MissingExtension(e) => e.fmt(f),
+			Self::NotExportable(t) => write!(f, "memory is not exportable as {:?}", t),
+			Self::TooManyObjects => write!(f, "too many objects"),
+			Self::OutOfMemory(e) => e.fmt(f),
+			Self::Unexpected(r) => write!(f, "unexpected driver error: {}", r)
+		}
+	}
+}
+
+impl std::error::Error for ExportError {
+	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+		match self {
+			Self::MissingExtension(e) => Some(e),
+			Self::OutOfMemory(e) => Some(e),
+			_ => None
+		}
+	}
+}
+
+impl From<MissingExtensionError> for ExportError {
+	fn from(e: MissingExtensionError) -> Self {
+		Self::MissingExtension(e)
+	}
+}
+
+impl From<vk::Result> for ExportError {
+	fn from(r: vk::Result) -> ExportError {
+		match r {
+			vk::Result::ERROR_OUT_OF_HOST_MEMORY => ExportError::OutOfMemory(OomError::Host),
+			vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => ExportError::OutOfMemory(OomError::Device),
+			vk::Result::ERROR_TOO_MANY_OBJECTS => ExportError::TooManyObjects,
+			r => ExportError::Unexpected(r)
+		}
+	}
+}
@@ -61,3 +61,35 @@ features! {
 	variable_multisample_rate: VariableMultisampleRate => variable_multisample_rate: "Variable Multisample Rate",
 	inherited_queries: InheritedQueries => inherited_queries: "Inherited Queries",
 }
+
+/// Features of the `VK_KHR_buffer_device_address` extension.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct BufferDeviceAddressFeatures {
+	/// Buffer device addresses can be queried and used in shaders.
+	pub buffer_device_address: bool,
+
+	/// Buffer and memory addresses can be captured and replayed by tools.
+	pub capture_replay: bool,
+
+	/// Buffer device addresses are supported on logical devices created with multiple physical devices.
+	pub multi_device: bool
+}
+
+impl BufferDeviceAddressFeatures {
+	pub(crate) fn from_vulkan(features: &ash::vk::PhysicalDeviceBufferDeviceAddressFeatures) -> Self {
+		Self {
+			buffer_device_address: features.buffer_device_address != ash::vk::FALSE,
+			capture_replay: features.buffer_device_address_capture_replay != ash::vk::FALSE,
+			multi_device: features.buffer_device_address_multi_device != ash::vk::FALSE
+		}
+	}
+
+	pub(crate) fn into_vulkan(self) -> ash::vk::PhysicalDeviceBufferDeviceAddressFeatures {
+		ash::vk::PhysicalDeviceBufferDeviceAddressFeatures {
+			buffer_device_address: self.buffer_device_address as ash::vk::Bool32,
+			buffer_device_address_capture_replay: self.capture_replay as ash::vk::Bool32,
+			buffer_device_address_multi_device: self.multi_device as ash::vk::Bool32,
+			..Default::default()
+		}
+	}
+}
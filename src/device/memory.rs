@@ -13,6 +13,7 @@ use crate::{
 	Device,
 	DeviceOwned
 };
+use super::external;
 
 #[derive(Debug)]
 pub enum MapError {
@@ -51,6 +52,17 @@ impl From<vk::Result> for MapError {
 	}
 }
 
+/// Memory budget of a heap, as reported by `VK_EXT_memory_budget`.
+#[derive(Clone, Copy, Debug)]
+pub struct HeapBudget {
+	/// Estimated number of bytes the process can allocate from the heap
+	/// before allocations may fail or cause performance degradation.
+	pub budget: u64,
+
+	/// Estimated number of bytes currently allocated by the process from the heap.
+	pub usage: u64
+}
+
 /// A region of allocated device memory.
 pub struct Memory {
 	/// Underlying vulkan handle.
@@ -63,7 +75,10 @@ pub struct Memory {
 	memory_type_index: u32,
 	
 	/// Size (in bytes) of the memory region.
-	size: u64
+	size: u64,
+
+	/// Handle types the memory can be exported to.
+	export_handle_types: external::HandleTypes
 }
 
 impl Memory {
@@ -73,10 +88,17 @@ impl Memory {
 			handle,
 			device: device.clone(),
 			memory_type_index: memory_type.index(),
-			size
+			size,
+			export_handle_types: external::HandleTypes::none()
 		}
 	}
 
+	#[inline]
+	pub(crate) fn exportable(mut self, handle_types: external::HandleTypes) -> Memory {
+		self.export_handle_types = handle_types;
+		self
+	}
+
 	#[inline]
 	pub(crate) fn handle(&self) -> vk::DeviceMemory {
 		self.handle
@@ -92,6 +114,100 @@ impl Memory {
 		MemoryType::new(self.device.physical_device(), self.memory_type_index)
 	}
 
+	/// Handle types the memory can be exported to.
+	#[inline]
+	pub fn export_handle_types(&self) -> external::HandleTypes {
+		self.export_handle_types
+	}
+
+	/// Opaque capture address of the memory, used by tools to replay its address
+	/// with `Device::allocate_memory_for_capture_replay`.
+	///
+	/// The memory must have been allocated with `Device::allocate_memory_for_capture_replay`.
+	pub fn opaque_capture_address(&self) -> Result<u64, super::MissingExtensionError> {
+		let ext = self.device.ext_khr_buffer_device_address()?;
+		let infos = vk::DeviceMemoryOpaqueCaptureAddressInfo {
+			memory: self.handle,
+			..Default::default()
+		};
+
+		Ok(unsafe {
+			ext.get_device_memory_opaque_capture_address_khr(self.device.handle.handle(), &infos)
+		})
+	}
+
+	/// Exports the memory as a file descriptor of the given handle type.
+	///
+	/// The memory must have been allocated with `Device::allocate_exportable_memory`
+	/// including this handle type.
+	/// The returned file descriptor is owned by the caller.
+	#[cfg(unix)]
+	pub fn export_fd(&self, handle_type: external::HandleType) -> Result<std::os::unix::io::RawFd, external::ExportError> {
+		if !self.export_handle_types.contains(handle_type) {
+			return Err(external::ExportError::NotExportable(handle_type))
+		}
+
+		let ext = self.device.ext_khr_external_memory_fd()?;
+		let infos = vk::MemoryGetFdInfoKHR {
+			memory: self.handle,
+			handle_type: handle_type.into_vulkan(),
+			..Default::default()
+		};
+
+		let mut fd = -1;
+		unsafe {
+			match ext.get_memory_fd_khr(self.device.handle.handle(), &infos, &mut fd) {
+				vk::Result::SUCCESS => Ok(fd),
+				e => Err(e.into())
+			}
+		}
+	}
+
+	/// Returns the range of memory to flush or invalidate.
+	///
+	/// The offset must be a multiple of the `nonCoherentAtomSize` limit of the device,
+	/// and so must be the size unless the range ends at the end of the memory.
+	pub(crate) fn mapped_range(&self, offset: u64, size: u64) -> vk::MappedMemoryRange {
+		let atom_size = self.device.physical_device().limits().non_coherent_atom_size();
+		debug_assert!(offset % atom_size == 0);
+		debug_assert!(size % atom_size == 0 || offset + size == self.size);
+
+		vk::MappedMemoryRange {
+			memory: self.handle,
+			offset,
+			size,
+			..Default::default()
+		}
+	}
+
+	/// Makes host writes to the given range visible to the device.
+	///
+	/// This is only required for memory types that are not host coherent.
+	/// The memory must be mapped, and the range aligned to the `nonCoherentAtomSize` limit of the device
+	/// (its size may also reach the end of the memory).
+	#[inline]
+	pub fn flush(&self, offset: u64, size: u64) -> Result<(), OomError> {
+		unsafe {
+			self.device.handle.flush_mapped_memory_ranges(&[self.mapped_range(offset, size)])?
+		}
+
+		Ok(())
+	}
+
+	/// Makes device writes to the given range visible to the host.
+	///
+	/// This is only required for memory types that are not host coherent.
+	/// The memory must be mapped, and the range aligned to the `nonCoherentAtomSize` limit of the device
+	/// (its size may also reach the end of the memory).
+	#[inline]
+	pub fn invalidate(&self, offset: u64, size: u64) -> Result<(), OomError> {
+		unsafe {
+			self.device.handle.invalidate_mapped_memory_ranges(&[self.mapped_range(offset, size)])?
+		}
+
+		Ok(())
+	}
+
 	/// Map the memory to host address space.
 	#[inline]
 	pub fn map(self, offset: u64, size: Option<u64>) -> Result<MappedMemory, MapError> {
@@ -168,4 +284,60 @@ impl Drop for MappedMemory {
 			self.memory.device.handle.unmap_memory(self.memory.handle)
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// Reference-counted, persistently mapped memory.
+///
+/// Since the same memory cannot be mapped twice,
+/// this type allows multiple owners (such as the slots sub-allocated in a memory block)
+/// to share a single mapping of the whole memory and derive pointers from it.
+/// The memory is unmapped and freed when the last reference is dropped.
+#[derive(Clone)]
+pub struct SharedMappedMemory {
+	inner: Arc<MappedMemory>
+}
+
+impl SharedMappedMemory {
+	/// Maps the whole memory to host address space.
+	#[inline]
+	pub fn new(memory: Memory) -> Result<Self, MapError> {
+		Ok(Self {
+			inner: Arc::new(memory.map(0, None)?)
+		})
+	}
+
+	#[inline]
+	pub fn memory(&self) -> &Memory {
+		self.inner.as_memory()
+	}
+
+	/// Pointer to the beginning of the memory.
+	#[inline]
+	pub fn ptr(&self) -> *mut c_void {
+		self.inner.ptr()
+	}
+
+	/// Pointer to the given offset in the memory.
+	///
+	/// Returns `None` if the offset is out of bounds.
+	#[inline]
+	pub fn ptr_at(&self, offset: u64) -> Option<*mut c_void> {
+		if offset <= self.memory().size() {
+			Some(unsafe { (self.inner.ptr() as *mut u8).offset(offset as isize) as *mut c_void })
+		} else {
+			None
+		}
+	}
+
+	/// Returns the number of references to this memory.
+	#[inline]
+	pub fn reference_count(&self) -> usize {
+		Arc::strong_count(&self.inner)
+	}
+}
+
+impl DeviceOwned for SharedMappedMemory {
+	fn device(&self) -> &Arc<Device> {
+		self.memory().device()
+	}
+}
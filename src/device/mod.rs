@@ -17,6 +17,10 @@ use std::{
 use crate::{
 	OomError,
 	Instance,
+	mem::{
+		MemoryRequirements,
+		memory_requirements::Resource
+	},
 	instance::{
 		PhysicalDevice,
 		physical_device::{
@@ -30,6 +34,7 @@ pub mod extension;
 pub mod feature;
 pub mod queue;
 pub mod memory;
+pub mod external;
 
 pub use extension::{
 	Extension,
@@ -37,13 +42,16 @@ pub use extension::{
 };
 pub use feature::{
 	Feature,
-	Features
+	Features,
+	BufferDeviceAddressFeatures
 };
 use feature::IntoFFiFeatures;
 pub use queue::Queue;
 pub use memory::{
 	Memory,
-	MappedMemory
+	MappedMemory,
+	SharedMappedMemory,
+	HeapBudget
 };
 
 #[derive(Debug)]
@@ -52,6 +60,7 @@ pub enum CreationError {
 	InvalidQueuePriority(f32),
 	InitializationFailed,
 	MissingExtension(Extension),
+	MissingInstanceExtension(crate::instance::Extension),
 	MissingFeature(Feature),
 	TooManyObjets,
 	TooManyQueuesForFamily(u32, u32),
@@ -70,6 +79,7 @@ impl fmt::Display for CreationError {
 			InvalidQueuePriority(p) => write!(f, "invalid queue priority `{}`", p),
 			InitializationFailed => write!(f, "device initialization failed"),
 			MissingExtension(e) => write!(f, "missing device extension `{}`", e),
+			MissingInstanceExtension(e) => write!(f, "missing instance extension `{}`", e),
 			MissingFeature(t) => write!(f, "missing device feature `{}`", t),
 			TooManyObjets => write!(f, "too many objets"),
 			TooManyQueuesForFamily(index, max) => write!(f, "too many queues (>= {}) for the same queue family ({})", max, index),
@@ -96,6 +106,7 @@ impl From<vk::Result> for CreationError {
 #[derive(Debug)]
 pub enum AllocationError {
 	OutOfMemory(OomError),
+	MissingExtension(MissingExtensionError),
 	InvalidExternalHandle,
 	InvalidOpaqueCaptureAddress
 }
@@ -104,6 +115,7 @@ impl fmt::Display for AllocationError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
 			Self::OutOfMemory(e) => e.fmt(f),
+			Self::MissingExtension(e) => e.fmt(f),
 			Self::InvalidExternalHandle => write!(f, "invalid external handle"),
 			Self::InvalidOpaqueCaptureAddress => write!(f, "invalid opaque capture address")
 		}
@@ -114,11 +126,18 @@ impl std::error::Error for AllocationError {
 	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
 		match self {
 			Self::OutOfMemory(e) => Some(e),
+			Self::MissingExtension(e) => Some(e),
 			_ => None
 		}
 	}
 }
 
+impl From<MissingExtensionError> for AllocationError {
+	fn from(e: MissingExtensionError) -> AllocationError {
+		AllocationError::MissingExtension(e)
+	}
+}
+
 impl From<vk::Result> for AllocationError {
 	fn from(r: vk::Result) -> AllocationError {
 		match r {
@@ -144,12 +163,54 @@ impl fmt::Display for MissingExtensionError {
 	}
 }
 
+/// Memory budget query error.
+#[derive(Debug)]
+pub enum BudgetError {
+	MissingDeviceExtension(MissingExtensionError),
+	MissingInstanceExtension(crate::instance::MissingExtensionError)
+}
+
+impl std::error::Error for BudgetError {
+	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+		match self {
+			Self::MissingDeviceExtension(e) => Some(e),
+			_ => None
+		}
+	}
+}
+
+impl fmt::Display for BudgetError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::MissingDeviceExtension(e) => e.fmt(f),
+			Self::MissingInstanceExtension(e) => write!(f, "missing instance extension `{}`", e.0)
+		}
+	}
+}
+
+impl From<MissingExtensionError> for BudgetError {
+	fn from(e: MissingExtensionError) -> Self {
+		Self::MissingDeviceExtension(e)
+	}
+}
+
+impl From<crate::instance::MissingExtensionError> for BudgetError {
+	fn from(e: crate::instance::MissingExtensionError) -> Self {
+		Self::MissingInstanceExtension(e)
+	}
+}
+
 pub struct Device {
 	pub(crate) handle: ash::Device,
 	instance: Arc<Instance>,
 	physical_device_index: u32,
 	loaded_extensions: Extensions,
-	ext_khr_swapchain: OnceCell<ash::extensions::khr::Swapchain>
+	buffer_device_address: Option<BufferDeviceAddressFeatures>,
+	ext_khr_swapchain: OnceCell<ash::extensions::khr::Swapchain>,
+	ext_khr_get_memory_requirements2: OnceCell<vk::KhrGetMemoryRequirements2Fn>,
+	ext_khr_external_memory_fd: OnceCell<vk::KhrExternalMemoryFdFn>,
+	ext_khr_buffer_device_address: OnceCell<vk::KhrBufferDeviceAddressFn>,
+	ext_khr_timeline_semaphore: OnceCell<vk::KhrTimelineSemaphoreFn>
 }
 
 impl Device {
@@ -200,7 +261,52 @@ impl Device {
 
 		let ffi_features = features.into_ffi();
 
+		// Enabling `VK_KHR_buffer_device_address` enables its features, when supported.
+		// Memory bound to buffers with a device address is allocated with the `DEVICE_ADDRESS` flag,
+		// which requires `VK_KHR_device_group` on Vulkan 1.0, enabled here if needed.
+		// The optional features can only be queried with `VK_KHR_get_physical_device_properties2`,
+		// without it only the core feature (implied by the extension) is enabled.
+		let buffer_device_address = if loaded_extensions.khr_buffer_device_address {
+			let capture_replay = physical_device.buffer_device_address_features().map(|supported| supported.capture_replay).unwrap_or(false);
+
+			if !loaded_extensions.khr_device_group {
+				if !instance.loaded_extensions().khr_device_group_creation {
+					return Err(CreationError::MissingInstanceExtension(crate::instance::Extension::KhrDeviceGroupCreation))
+				}
+
+				loaded_extensions.insert(Extension::KhrDeviceGroup);
+				extension_names.push(Extension::KhrDeviceGroup.c_name().as_ptr())
+			}
+
+			Some(BufferDeviceAddressFeatures {
+				buffer_device_address: true,
+				capture_replay,
+				multi_device: false
+			})
+		} else {
+			None
+		};
+
+		let mut p_next: *mut std::ffi::c_void = std::ptr::null_mut();
+
+		let mut ffi_buffer_device_address = buffer_device_address.unwrap_or_default().into_vulkan();
+		if buffer_device_address.is_some() {
+			ffi_buffer_device_address.p_next = p_next;
+			p_next = &mut ffi_buffer_device_address as *mut _ as *mut std::ffi::c_void;
+		}
+
+		// The `timelineSemaphore` feature is required by `VK_KHR_timeline_semaphore`.
+		let mut ffi_timeline_semaphore = vk::PhysicalDeviceTimelineSemaphoreFeatures {
+			timeline_semaphore: vk::TRUE,
+			..Default::default()
+		};
+		if loaded_extensions.khr_timeline_semaphore {
+			ffi_timeline_semaphore.p_next = p_next;
+			p_next = &mut ffi_timeline_semaphore as *mut _ as *mut std::ffi::c_void;
+		}
+
 		let infos = vk::DeviceCreateInfo {
+			p_next,
 			queue_create_info_count: queue_create_infos.len() as u32,
 			p_queue_create_infos: queue_create_infos.as_ptr(),
 			enabled_extension_count: extension_names.len() as u32,
@@ -218,7 +324,12 @@ impl Device {
 			instance: instance.clone(),
 			physical_device_index: physical_device.index(),
 			loaded_extensions,
-			ext_khr_swapchain: OnceCell::new()
+			buffer_device_address,
+			ext_khr_swapchain: OnceCell::new(),
+			ext_khr_get_memory_requirements2: OnceCell::new(),
+			ext_khr_external_memory_fd: OnceCell::new(),
+			ext_khr_buffer_device_address: OnceCell::new(),
+			ext_khr_timeline_semaphore: OnceCell::new()
 		});
 
 		let queues = Queues {
@@ -238,21 +349,256 @@ impl Device {
 		PhysicalDevice::new(&self.instance, self.physical_device_index)
 	}
 
-	/// Allocate some device memory.
-	pub fn allocate_memory(self: &Arc<Self>, memory_type: MemoryType, size: u64) -> Result<Memory, AllocationError> {
+	#[inline]
+	pub fn loaded_extensions(&self) -> &Extensions {
+		&self.loaded_extensions
+	}
+
+	/// Enabled features of the `VK_KHR_buffer_device_address` extension, if enabled.
+	///
+	/// The extension features are enabled when the extension is,
+	/// except for multi-device addresses.
+	/// Capture and replay is enabled if supported by the physical device and
+	/// if the `VK_KHR_get_physical_device_properties2` instance extension is enabled.
+	#[inline]
+	pub fn buffer_device_address_features(&self) -> Option<BufferDeviceAddressFeatures> {
+		self.buffer_device_address
+	}
+
+	/// Allocates raw device memory.
+	///
+	/// If buffer device addresses are enabled, the `DEVICE_ADDRESS` allocation flag is set
+	/// so that buffers bound to the memory can have a device address.
+	/// If `opaque_capture_address` is not `None`, the memory is allocated for capture (`Some(0)`)
+	/// or replay of its address.
+	unsafe fn allocate_raw(&self, memory_type: &MemoryType, size: u64, p_next: *const std::ffi::c_void, opaque_capture_address: Option<u64>) -> Result<vk::DeviceMemory, AllocationError> {
+		let mut flags = vk::MemoryAllocateFlags::empty();
+
+		if self.buffer_device_address.is_some() {
+			flags |= vk::MemoryAllocateFlags::DEVICE_ADDRESS
+		}
+
+		let capture_infos = vk::MemoryOpaqueCaptureAddressAllocateInfo {
+			p_next,
+			opaque_capture_address: opaque_capture_address.unwrap_or(0),
+			..Default::default()
+		};
+
+		let p_next = match opaque_capture_address {
+			Some(_) => {
+				flags |= vk::MemoryAllocateFlags::DEVICE_ADDRESS_CAPTURE_REPLAY;
+				&capture_infos as *const _ as *const std::ffi::c_void
+			},
+			None => p_next
+		};
+
+		let flags_infos = vk::MemoryAllocateFlagsInfo {
+			p_next,
+			flags,
+			..Default::default()
+		};
+
 		let infos = vk::MemoryAllocateInfo {
+			p_next: if flags.is_empty() {
+				p_next
+			} else {
+				&flags_infos as *const _ as *const std::ffi::c_void
+			},
 			allocation_size: size,
 			memory_type_index: memory_type.index(),
 			..Default::default()
 		};
 
+		Ok(self.handle.allocate_memory(&infos, None)?)
+	}
+
+	/// Allocate some device memory.
+	pub fn allocate_memory(self: &Arc<Self>, memory_type: MemoryType, size: u64) -> Result<Memory, AllocationError> {
+		let handle = unsafe {
+			self.allocate_raw(&memory_type, size, std::ptr::null(), None)?
+		};
+
+		Ok(Memory::new(self, memory_type, size, handle))
+	}
+
+	/// Allocate some device memory whose opaque address can be captured and replayed by tools.
+	///
+	/// If `opaque_capture_address` is 0, the memory is allocated for capture,
+	/// and its address can be retrieved with `Memory::opaque_capture_address`.
+	/// Otherwise, the memory is allocated at the given previously captured address.
+	///
+	/// ## Panics
+	///
+	/// This function panics if buffer device address capture and replay is not enabled
+	/// (see `buffer_device_address_features`).
+	pub fn allocate_memory_for_capture_replay(self: &Arc<Self>, memory_type: MemoryType, size: u64, opaque_capture_address: u64) -> Result<Memory, AllocationError> {
+		assert!(self.buffer_device_address.map(|f| f.capture_replay).unwrap_or(false), "buffer device address capture and replay is not enabled");
+
 		let handle = unsafe {
-			self.handle.allocate_memory(&infos, None)?
+			self.allocate_raw(&memory_type, size, std::ptr::null(), Some(opaque_capture_address))?
 		};
 
 		Ok(Memory::new(self, memory_type, size, handle))
 	}
 
+	/// Allocate some device memory dedicated to the resource described by the given memory requirements.
+	///
+	/// The size of the allocated memory is the size of the requirements.
+	/// If the `VK_KHR_dedicated_allocation` extension is not enabled,
+	/// this is equivalent to a regular allocation.
+	pub fn allocate_dedicated_memory(self: &Arc<Self>, memory_type: MemoryType, memory_requirements: &MemoryRequirements) -> Result<Memory, AllocationError> {
+		if !self.loaded_extensions.khr_dedicated_allocation {
+			return self.allocate_memory(memory_type, memory_requirements.size())
+		}
+
+		let dedicated_infos = dedicated_allocate_info(memory_requirements.resource());
+
+		let handle = unsafe {
+			self.allocate_raw(&memory_type, memory_requirements.size(), &dedicated_infos as *const _ as *const std::ffi::c_void, None)?
+		};
+
+		Ok(Memory::new(self, memory_type, memory_requirements.size(), handle))
+	}
+
+	/// Allocate some device memory that can be exported to other APIs or processes
+	/// with `Memory::export_fd`.
+	///
+	/// The size of the allocated memory is the size of the requirements.
+	/// If `dedicated` is true, the memory is dedicated to the resource described by the requirements
+	/// (see `allocate_dedicated_memory`).
+	/// The handle types should be negotiated with `PhysicalDevice::external_buffer_properties`
+	/// or `PhysicalDevice::external_image_properties`,
+	/// and the resource created with the same handle types.
+	///
+	/// This requires the `VK_KHR_external_memory` extension to be enabled.
+	pub fn allocate_exportable_memory(self: &Arc<Self>, memory_type: MemoryType, memory_requirements: &MemoryRequirements, handle_types: external::HandleTypes, dedicated: bool) -> Result<Memory, AllocationError> {
+		if !self.loaded_extensions.khr_external_memory {
+			return Err(MissingExtensionError(Extension::KhrExternalMemory).into())
+		}
+
+		let dedicated_infos = dedicated_allocate_info(memory_requirements.resource());
+
+		let export_infos = vk::ExportMemoryAllocateInfo {
+			p_next: if dedicated && self.loaded_extensions.khr_dedicated_allocation {
+				&dedicated_infos as *const _ as *const std::ffi::c_void
+			} else {
+				std::ptr::null()
+			},
+			handle_types: handle_types.into_vulkan(),
+			..Default::default()
+		};
+
+		let handle = unsafe {
+			self.allocate_raw(&memory_type, memory_requirements.size(), &export_infos as *const _ as *const std::ffi::c_void, None)?
+		};
+
+		Ok(Memory::new(self, memory_type, memory_requirements.size(), handle).exportable(handle_types))
+	}
+
+	/// Import device memory from a file descriptor.
+	///
+	/// The size of the imported memory is the size of the requirements.
+	/// If `dedicated` is true, the memory is dedicated to the resource described by the requirements,
+	/// which must be the case if the memory was exported as dedicated memory.
+	/// The resource must be created with the handle type of the file descriptor.
+	///
+	/// On success, the file descriptor is owned by the returned memory and must not be used anymore.
+	/// Returns `AllocationError::InvalidExternalHandle` if the file descriptor cannot be imported.
+	///
+	/// This requires the `VK_KHR_external_memory_fd` extension to be enabled
+	/// (and `VK_EXT_external_memory_dma_buf` for dma-buf file descriptors).
+	///
+	/// ## Safety
+	///
+	/// The file descriptor must be a valid handle of the given type,
+	/// compatible with the memory type (see `memory_fd_properties`).
+	#[cfg(unix)]
+	pub unsafe fn import_memory_fd(self: &Arc<Self>, memory_type: MemoryType, memory_requirements: &MemoryRequirements, handle_type: external::HandleType, fd: std::os::unix::io::RawFd, dedicated: bool) -> Result<Memory, AllocationError> {
+		self.check_fd_handle_type(handle_type)?;
+
+		let dedicated_infos = dedicated_allocate_info(memory_requirements.resource());
+
+		let import_infos = vk::ImportMemoryFdInfoKHR {
+			p_next: if dedicated && self.loaded_extensions.khr_dedicated_allocation {
+				&dedicated_infos as *const _ as *const std::ffi::c_void
+			} else {
+				std::ptr::null()
+			},
+			handle_type: handle_type.into_vulkan(),
+			fd,
+			..Default::default()
+		};
+
+		let handle = self.allocate_raw(&memory_type, memory_requirements.size(), &import_infos as *const _ as *const std::ffi::c_void, None)?;
+		Ok(Memory::new(self, memory_type, memory_requirements.size(), handle))
+	}
+
+	/// Returns the memory type bits into which the given file descriptor can be imported.
+	///
+	/// Opaque file descriptors must be imported into the memory type they were exported from,
+	/// so this is only meaningful for other handle types.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the handle type is `HandleType::OpaqueFd`.
+	#[cfg(unix)]
+	pub fn memory_fd_properties(&self, handle_type: external::HandleType, fd: std::os::unix::io::RawFd) -> Result<u32, AllocationError> {
+		assert!(handle_type != external::HandleType::OpaqueFd, "opaque file descriptors have no memory properties");
+		self.check_fd_handle_type(handle_type)?;
+
+		let ext = self.ext_khr_external_memory_fd()?;
+		let mut properties = vk::MemoryFdPropertiesKHR::default();
+		unsafe {
+			match ext.get_memory_fd_properties_khr(self.handle.handle(), handle_type.into_vulkan(), fd, &mut properties) {
+				vk::Result::SUCCESS => Ok(properties.memory_type_bits),
+				e => Err(e.into())
+			}
+		}
+	}
+
+	/// Checks that the extensions required by the given file descriptor handle type are enabled.
+	fn check_fd_handle_type(&self, handle_type: external::HandleType) -> Result<(), MissingExtensionError> {
+		if !self.loaded_extensions.khr_external_memory_fd {
+			return Err(MissingExtensionError(Extension::KhrExternalMemoryFd))
+		}
+
+		if handle_type == external::HandleType::DmaBuf && !self.loaded_extensions.ext_external_memory_dma_buf {
+			return Err(MissingExtensionError(Extension::ExtExternalMemoryDmaBuf))
+		}
+
+		Ok(())
+	}
+
+	/// Returns the memory budget of each memory heap.
+	///
+	/// This requires the `VK_EXT_memory_budget` device extension and
+	/// the `VK_KHR_get_physical_device_properties2` instance extension to be enabled.
+	pub fn memory_budget(&self) -> Result<Vec<HeapBudget>, BudgetError> {
+		if !self.loaded_extensions.ext_memory_budget {
+			return Err(MissingExtensionError(Extension::ExtMemoryBudget).into())
+		}
+
+		let ext = self.instance.ext_khr_get_physical_device_properties2()?;
+
+		let mut budget_properties = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+		let mut properties = vk::PhysicalDeviceMemoryProperties2 {
+			p_next: &mut budget_properties as *mut _ as *mut std::ffi::c_void,
+			..Default::default()
+		};
+
+		let physical_device = self.physical_device();
+		unsafe {
+			ext.get_physical_device_memory_properties2_khr(physical_device.handle(), &mut properties);
+		}
+
+		Ok((0..physical_device.memory_heap_count() as usize).map(|i| {
+			HeapBudget {
+				budget: budget_properties.heap_budget[i],
+				usage: budget_properties.heap_usage[i]
+			}
+		}).collect())
+	}
+
 	pub fn ext_khr_swapchain(&self) -> Result<&ash::extensions::khr::Swapchain, MissingExtensionError> {
 		self.ext_khr_swapchain.get_or_try_init(|| {
 			if self.loaded_extensions.khr_swapchain {
@@ -262,6 +608,67 @@ impl Device {
 			}
 		})
 	}
+
+	pub fn ext_khr_get_memory_requirements2(&self) -> Result<&vk::KhrGetMemoryRequirements2Fn, MissingExtensionError> {
+		self.ext_khr_get_memory_requirements2.get_or_try_init(|| {
+			if self.loaded_extensions.khr_get_memory_requirements2 {
+				Ok(vk::KhrGetMemoryRequirements2Fn::load(|name| unsafe {
+					std::mem::transmute(self.instance.handle.get_device_proc_addr(self.handle.handle(), name.as_ptr()))
+				}))
+			} else {
+				Err(MissingExtensionError(Extension::KhrGetMemoryRequirements2))
+			}
+		})
+	}
+
+	pub fn ext_khr_external_memory_fd(&self) -> Result<&vk::KhrExternalMemoryFdFn, MissingExtensionError> {
+		self.ext_khr_external_memory_fd.get_or_try_init(|| {
+			if self.loaded_extensions.khr_external_memory_fd {
+				Ok(vk::KhrExternalMemoryFdFn::load(|name| unsafe {
+					std::mem::transmute(self.instance.handle.get_device_proc_addr(self.handle.handle(), name.as_ptr()))
+				}))
+			} else {
+				Err(MissingExtensionError(Extension::KhrExternalMemoryFd))
+			}
+		})
+	}
+
+	pub fn ext_khr_buffer_device_address(&self) -> Result<&vk::KhrBufferDeviceAddressFn, MissingExtensionError> {
+		self.ext_khr_buffer_device_address.get_or_try_init(|| {
+			if self.loaded_extensions.khr_buffer_device_address {
+				Ok(vk::KhrBufferDeviceAddressFn::load(|name| unsafe {
+					std::mem::transmute(self.instance.handle.get_device_proc_addr(self.handle.handle(), name.as_ptr()))
+				}))
+			} else {
+				Err(MissingExtensionError(Extension::KhrBufferDeviceAddress))
+			}
+		})
+	}
+
+	pub fn ext_khr_timeline_semaphore(&self) -> Result<&vk::KhrTimelineSemaphoreFn, MissingExtensionError> {
+		self.ext_khr_timeline_semaphore.get_or_try_init(|| {
+			if self.loaded_extensions.khr_timeline_semaphore {
+				Ok(vk::KhrTimelineSemaphoreFn::load(|name| unsafe {
+					std::mem::transmute(self.instance.handle.get_device_proc_addr(self.handle.handle(), name.as_ptr()))
+				}))
+			} else {
+				Err(MissingExtensionError(Extension::KhrTimelineSemaphore))
+			}
+		})
+	}
+}
+
+fn dedicated_allocate_info(resource: Resource) -> vk::MemoryDedicatedAllocateInfo {
+	match resource {
+		Resource::Buffer(buffer) => vk::MemoryDedicatedAllocateInfo {
+			buffer,
+			..Default::default()
+		},
+		Resource::Image(image) => vk::MemoryDedicatedAllocateInfo {
+			image,
+			..Default::default()
+		}
+	}
 }
 
 impl PartialEq for Device {
@@ -13,6 +13,11 @@ use crate::{
 	pipeline,
 	device,
 	DeviceOwned,
+	mem::{
+		Slot,
+		sparse,
+		buffer::usage
+	},
 	sync::{
 		self,
 		task,
@@ -22,12 +27,22 @@ use crate::{
 };
 use super::Device;
 
+mod batch;
+
+pub use batch::*;
+
 #[derive(Debug)]
 pub enum SubmitError {
 	OutOfMemory(OomError),
 	DeviceLost
 }
 
+impl From<OomError> for SubmitError {
+	fn from(e: OomError) -> SubmitError {
+		SubmitError::OutOfMemory(e)
+	}
+}
+
 impl From<vk::Result> for SubmitError {
 	fn from(r: vk::Result) -> SubmitError {
 		match r {
@@ -57,6 +72,9 @@ pub enum PresentError {
 	OutOfMemory(OomError),
 	DeviceLost,
 	MissingDeviceExtension(device::MissingExtensionError),
+
+	/// The presentation waits on timeline semaphores, which is not supported.
+	TimelineSemaphore
 }
 
 impl From<device::MissingExtensionError> for PresentError {
@@ -85,7 +103,8 @@ impl fmt::Display for PresentError {
 		match self {
 			Self::OutOfMemory(e) => e.fmt(f),
 			Self::DeviceLost => write!(f, "device lost"),
-			Self::MissingDeviceExtension(e) => e.fmt(f)
+			Self::MissingDeviceExtension(e) => e.fmt(f),
+			Self::TimelineSemaphore => write!(f, "presentation cannot wait on timeline semaphores")
 		}
 	}
 }
@@ -129,6 +148,30 @@ impl Queue {
 		}
 	}
 
+	/// Starts a batched submission on this queue.
+	pub fn batch<'a, 'k, B: command::RecordedBuffer>(&'a self) -> Batch<'a, 'k, B> {
+		Batch::new(self)
+	}
+
+	/// Starts a sparse binding operation on this queue.
+	///
+	/// The queue family must support sparse binding.
+	pub fn bind_sparse<'a, S: Slot>(&'a self) -> BindSparse<'a, S> {
+		debug_assert!(self.family().supports_sparse_binding());
+
+		BindSparse {
+			queue: self,
+			buffer_binds: Vec::new(),
+			image_opaque_binds: Vec::new(),
+			image_binds: Vec::new(),
+			updates: Vec::new()
+		}
+	}
+
+	/// Presents the given swapchain image.
+	///
+	/// The presentation can only wait on binary semaphores:
+	/// executing it after a future signaling a timeline semaphore fails with `PresentError::TimelineSemaphore`.
 	pub fn present<'a, W>(&'a self, swapchain: &'a crate::Swapchain<W>, index: u32) -> Present<'a, W> {
 		Present {
 			queue: self,
@@ -144,6 +187,33 @@ impl DeviceOwned for Queue {
 	}
 }
 
+/// Timeline semaphore values of a submission, if some of its semaphores are timeline semaphores.
+fn timeline_semaphore_infos(wait_values: Option<&[u64]>, signal_semaphores: Option<&[vk::Semaphore]>, signal_values: Option<&[u64]>) -> Option<vk::TimelineSemaphoreSubmitInfo> {
+	if wait_values.is_none() && signal_values.is_none() {
+		return None
+	}
+
+	if let Some(values) = signal_values {
+		debug_assert_eq!(values.len(), signal_semaphores.map(|s| s.len()).unwrap_or(0));
+	}
+
+	Some(vk::TimelineSemaphoreSubmitInfo {
+		wait_semaphore_value_count: wait_values.map(|v| v.len() as u32).unwrap_or(0),
+		p_wait_semaphore_values: wait_values.map(|v| v.as_ptr()).unwrap_or(std::ptr::null()),
+		signal_semaphore_value_count: signal_values.map(|v| v.len() as u32).unwrap_or(0),
+		p_signal_semaphore_values: signal_values.map(|v| v.as_ptr()).unwrap_or(std::ptr::null()),
+		..Default::default()
+	})
+}
+
+#[inline]
+fn timeline_p_next(infos: &Option<vk::TimelineSemaphoreSubmitInfo>) -> *const std::ffi::c_void {
+	match infos {
+		Some(infos) => infos as *const _ as *const std::ffi::c_void,
+		None => std::ptr::null()
+	}
+}
+
 pub struct Submit<'a, B: command::RecordedBuffer> {
 	queue: &'a Queue,
 	buffer: B
@@ -160,6 +230,7 @@ unsafe impl<'a, B: command::RecordedBuffer> task::WaitPipelineStages for Submit<
 		past: Option<&P>,
 		wait_pipeline_stage_mask: Option<&[pipeline::stage::Flags]>,
 		signal_semaphores: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<((), Self::Payload), SubmitError> {
 		let (wait_semaphore_count, p_wait_semaphores) = match past {
@@ -170,7 +241,12 @@ unsafe impl<'a, B: command::RecordedBuffer> task::WaitPipelineStages for Submit<
 			None => (0, std::ptr::null())
 		};
 
+		self.buffer.flush_host_writes()?;
+
+		let timeline_infos = timeline_semaphore_infos(past.and_then(|p| p.semaphore_values()), signal_semaphores, signal_semaphore_values);
+
 		let infos = vk::SubmitInfo {
+			p_next: timeline_p_next(&timeline_infos),
 			wait_semaphore_count,
 			p_wait_semaphores,
 			p_wait_dst_stage_mask: wait_pipeline_stage_mask.map(|s| s.as_ptr()).unwrap_or(std::ptr::null()),
@@ -193,6 +269,7 @@ unsafe impl<'a, B: command::RecordedBuffer> task::WaitPipelineStages for Submit<
 }
 
 impl<'a, B: command::RecordedBuffer> task::SignalSemaphore for Submit<'a, B> {}
+impl<'a, B: command::RecordedBuffer> task::SignalTimeline for Submit<'a, B> {}
 impl<'a, B: command::RecordedBuffer> task::SignalFence for Submit<'a, B> {}
 
 pub struct Present<'a, W> {
@@ -210,10 +287,16 @@ unsafe impl<'a, W> task::Wait for Present<'a, W> {
 		self,
 		past: Option<&P>,
 		_signal_semaphores: Option<&[vk::Semaphore]>,
+		_signal_semaphore_values: Option<&[u64]>,
 		_signal_fence: Option<vk::Fence>,
 	) -> Result<(bool, ()), PresentError> {
 		let (wait_semaphore_count, p_wait_semaphores) = match past {
 			Some(past) => {
+				// Presentation can only wait on binary semaphores.
+				if past.semaphore_values().is_some() {
+					return Err(PresentError::TimelineSemaphore)
+				}
+
 				(past.semaphores().len() as u32, past.semaphores().as_ptr())
 			},
 			None => (0, std::ptr::null())
@@ -244,4 +327,168 @@ unsafe impl<'a, W> task::Wait for Present<'a, W> {
 
 		Ok((suboptimal, ()))
 	}
-}
\ No newline at end of file
+}
+
+/// Sparse binding operation.
+///
+/// The resources are borrowed by the operation,
+/// and their page tables are only updated once it is successfully submitted.
+/// The slots unbound by the operation are released once it is complete.
+pub struct BindSparse<'a, S: Slot> {
+	queue: &'a Queue,
+	buffer_binds: Vec<(vk::Buffer, Vec<vk::SparseMemoryBind>)>,
+	image_opaque_binds: Vec<(vk::Image, Vec<vk::SparseMemoryBind>)>,
+	image_binds: Vec<(vk::Image, Vec<vk::SparseImageMemoryBind>)>,
+
+	/// Page table updates of each bound resource.
+	updates: Vec<(&'a mut sparse::PageTable<S>, Vec<sparse::Update<S>>)>
+}
+
+/// Pushes a binding, grouping consecutive bindings of the same resource.
+fn push_bind<H: PartialEq, T>(binds: &mut Vec<(H, Vec<T>)>, handle: H, bind: T) {
+	match binds.last_mut() {
+		Some((h, list)) if *h == handle => list.push(bind),
+		_ => binds.push((handle, vec![bind]))
+	}
+}
+
+impl<'a, S: Slot> BindSparse<'a, S> {
+	/// Binds the given pages of a sparse buffer to the given slots, or unbinds them if `None`.
+	///
+	/// ## Safety
+	///
+	/// The pages must not be in use by the device while the operation executes.
+	pub unsafe fn bind_buffer_pages<U, I>(&mut self, buffer: &'a mut sparse::Buffer<S, U>, pages: I)
+	where
+		U: usage::Set,
+		I: IntoIterator<Item=(u64, Option<S>)>
+	{
+		let handle = crate::resource::Reference::handle(buffer);
+		let updates = pages.into_iter().map(|(page, slot)| {
+			let (bind, update) = buffer.bind_page(page, slot);
+			push_bind(&mut self.buffer_binds, handle, bind);
+			update
+		}).collect();
+
+		self.updates.push((buffer.pages_mut(), updates))
+	}
+
+	/// Binds the given tiles and opaque memory ranges of a sparse image to the given slots,
+	/// or unbinds them if `None`.
+	///
+	/// Tiles can only be bound for partially resident images.
+	/// Opaque ranges are used to bind the mip tail of partially resident images,
+	/// or the whole memory of the other sparse images.
+	///
+	/// ## Safety
+	///
+	/// The tiles and ranges must not be in use by the device while the operation executes.
+	pub unsafe fn bind_image<T, O>(&mut self, image: &'a mut sparse::Image<S>, tiles: T, opaque: O)
+	where
+		T: IntoIterator<Item=(sparse::Tile, Option<S>)>,
+		O: IntoIterator<Item=(std::ops::Range<u64>, Option<S>)>
+	{
+		let handle = crate::resource::Reference::handle(image);
+		let mut updates = Vec::new();
+
+		for (tile, slot) in tiles {
+			let (bind, update) = image.bind_tile(tile, slot);
+			push_bind(&mut self.image_binds, handle, bind);
+			updates.push(update)
+		}
+
+		for (range, slot) in opaque {
+			let (bind, update) = image.bind_opaque(range, slot);
+			push_bind(&mut self.image_opaque_binds, handle, bind);
+			updates.push(update)
+		}
+
+		self.updates.push((image.pages_mut(), updates))
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.buffer_binds.is_empty() && self.image_opaque_binds.is_empty() && self.image_binds.is_empty()
+	}
+}
+
+unsafe impl<'a, S: Slot> task::Wait for BindSparse<'a, S> {
+	type Output = ();
+	type Error = SubmitError;
+	type Payload = sparse::Released<S>;
+
+	/// Submit the sparse binding operation.
+	fn execute<P: future::SignalSemaphores>(
+		self,
+		past: Option<&P>,
+		signal_semaphores: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
+		signal_fence: Option<vk::Fence>,
+	) -> Result<((), Self::Payload), SubmitError> {
+		let (wait_semaphore_count, p_wait_semaphores) = match past {
+			Some(past) => {
+				(past.semaphores().len() as u32, past.semaphores().as_ptr())
+			},
+			None => (0, std::ptr::null())
+		};
+
+		let timeline_infos = timeline_semaphore_infos(past.and_then(|p| p.semaphore_values()), signal_semaphores, signal_semaphore_values);
+
+		let buffer_binds: Vec<_> = self.buffer_binds.iter().map(|(buffer, binds)| vk::SparseBufferMemoryBindInfo {
+			buffer: *buffer,
+			bind_count: binds.len() as u32,
+			p_binds: binds.as_ptr()
+		}).collect();
+
+		let image_opaque_binds: Vec<_> = self.image_opaque_binds.iter().map(|(image, binds)| vk::SparseImageOpaqueMemoryBindInfo {
+			image: *image,
+			bind_count: binds.len() as u32,
+			p_binds: binds.as_ptr()
+		}).collect();
+
+		let image_binds: Vec<_> = self.image_binds.iter().map(|(image, binds)| vk::SparseImageMemoryBindInfo {
+			image: *image,
+			bind_count: binds.len() as u32,
+			p_binds: binds.as_ptr()
+		}).collect();
+
+		let infos = vk::BindSparseInfo {
+			p_next: timeline_p_next(&timeline_infos),
+			wait_semaphore_count,
+			p_wait_semaphores,
+
+			buffer_bind_count: buffer_binds.len() as u32,
+			p_buffer_binds: buffer_binds.as_ptr(),
+			image_opaque_bind_count: image_opaque_binds.len() as u32,
+			p_image_opaque_binds: image_opaque_binds.as_ptr(),
+			image_bind_count: image_binds.len() as u32,
+			p_image_binds: image_binds.as_ptr(),
+
+			signal_semaphore_count: signal_semaphores.map(|s| s.len() as u32).unwrap_or(0),
+			p_signal_semaphores: signal_semaphores.map(|s| s.as_ptr()).unwrap_or(std::ptr::null()),
+			..Default::default()
+		};
+
+		unsafe {
+			let handle = self.queue.handle.lock();
+			let device = self.queue.device.handle();
+			match device.fp_v1_0().queue_bind_sparse(*handle, 1, &infos, signal_fence.unwrap_or(vk::Fence::null())) {
+				vk::Result::SUCCESS => (),
+				e => return Err(e.into())
+			}
+		}
+
+		let mut released = Vec::new();
+		for (pages, updates) in self.updates {
+			for update in updates {
+				released.extend(pages.apply(update))
+			}
+		}
+
+		Ok(((), sparse::Released::new(released)))
+	}
+}
+
+impl<'a, S: Slot> task::SignalSemaphore for BindSparse<'a, S> {}
+impl<'a, S: Slot> task::SignalTimeline for BindSparse<'a, S> {}
+impl<'a, S: Slot> task::SignalFence for BindSparse<'a, S> {}
@@ -0,0 +1,242 @@
+use ash::{
+	vk,
+	version::DeviceV1_0
+};
+use crate::{
+	command,
+	pipeline,
+	sync::{
+		task,
+		future,
+		Semaphore
+	}
+};
+use super::{
+	Queue,
+	SubmitError
+};
+
+/// Wait and signal sets of a single submit batch.
+#[derive(Default)]
+struct Infos {
+	wait_semaphores: Vec<vk::Semaphore>,
+	wait_values: Vec<u64>,
+	wait_pipeline_stage_masks: Vec<pipeline::stage::Flags>,
+	command_buffers: Vec<vk::CommandBuffer>,
+	signal_semaphores: Vec<vk::Semaphore>,
+	signal_values: Vec<u64>,
+	timeline: bool
+}
+
+impl Infos {
+	fn wait<F: future::SignalSemaphores>(&mut self, future: &F, wait_pipeline_stage_masks: &[pipeline::stage::Flags]) {
+		let semaphores = future.semaphores();
+		debug_assert_eq!(semaphores.len(), wait_pipeline_stage_masks.len());
+
+		self.wait_semaphores.extend_from_slice(semaphores);
+		match future.semaphore_values() {
+			Some(values) => {
+				self.wait_values.extend_from_slice(values);
+				self.timeline = true
+			},
+			None => self.wait_values.extend(semaphores.iter().map(|_| 0)) // ignored for binary semaphores.
+		}
+		self.wait_pipeline_stage_masks.extend_from_slice(wait_pipeline_stage_masks)
+	}
+
+	fn signal(&mut self, semaphores: &[vk::Semaphore], values: Option<&[u64]>) {
+		self.signal_semaphores.extend_from_slice(semaphores);
+		match values {
+			Some(values) => {
+				self.signal_values.extend_from_slice(values);
+				self.timeline = true
+			},
+			None => self.signal_values.extend(semaphores.iter().map(|_| 0)) // ignored for binary semaphores.
+		}
+	}
+
+	fn timeline_infos(&self) -> Option<vk::TimelineSemaphoreSubmitInfo> {
+		if self.timeline {
+			Some(vk::TimelineSemaphoreSubmitInfo {
+				wait_semaphore_value_count: self.wait_values.len() as u32,
+				p_wait_semaphore_values: self.wait_values.as_ptr(),
+				signal_semaphore_value_count: self.signal_values.len() as u32,
+				p_signal_semaphore_values: self.signal_values.as_ptr(),
+				..Default::default()
+			})
+		} else {
+			None
+		}
+	}
+}
+
+/// Batched submission.
+///
+/// Collects command buffers into several submit batches, each with its own wait and signal sets,
+/// executed in order by a single `vkQueueSubmit`.
+///
+/// When executed as a task, the past futures are waited on by the first batch,
+/// and the semaphores and fence are signaled by the last one.
+///
+/// The futures and semaphores kept until the submission completes may outlive the queue borrow `'a`,
+/// as they end up in the payload of the submission.
+pub struct Batch<'a, 'k, B> {
+	queue: &'a Queue,
+	batches: Vec<Infos>,
+	buffers: Vec<B>,
+	futures: Vec<Box<dyn future::Futures + Send + Sync + 'k>>,
+	kept: Vec<Box<dyn Send + Sync + 'k>>
+}
+
+impl<'a, 'k, B: command::RecordedBuffer> Batch<'a, 'k, B> {
+	pub(crate) fn new(queue: &'a Queue) -> Self {
+		Batch {
+			queue,
+			batches: vec![Infos::default()],
+			buffers: Vec::new(),
+			futures: Vec::new(),
+			kept: Vec::new()
+		}
+	}
+
+	#[inline]
+	fn current(&mut self) -> &mut Infos {
+		self.batches.last_mut().unwrap()
+	}
+
+	/// Add a command buffer to the current batch.
+	pub fn push(&mut self, buffer: B) {
+		let handle = buffer.handle();
+		self.current().command_buffers.push(handle);
+		self.buffers.push(buffer)
+	}
+
+	/// Make the current batch wait on the given future at the given pipeline stages.
+	///
+	/// The future is kept until the whole submission completes.
+	pub fn wait<F: 'k + Send + Sync + future::SignalSemaphores>(&mut self, future: F, wait_pipeline_stage_mask: pipeline::stage::Flags) {
+		let wait_pipeline_stage_masks = vec![wait_pipeline_stage_mask; future.semaphores().len()];
+		self.current().wait(&future, &wait_pipeline_stage_masks);
+		self.futures.push(Box::new(future))
+	}
+
+	/// Start a new batch, executed once the current one has signaled the given semaphore.
+	///
+	/// The new batch waits on the semaphore at the given pipeline stages.
+	/// The semaphore is kept until the whole submission completes.
+	pub fn then<S: 'k + Send + Sync + Semaphore>(&mut self, semaphore: S, wait_pipeline_stage_mask: pipeline::stage::Flags) {
+		let handle = *semaphore.handle();
+		self.current().signal(&[handle], None);
+		self.batches.push(Infos::default());
+		self.current().wait_semaphores.push(handle);
+		self.current().wait_values.push(0);
+		self.current().wait_pipeline_stage_masks.push(wait_pipeline_stage_mask);
+		self.kept.push(Box::new(semaphore))
+	}
+
+	/// Start a new batch, not depending on the previous ones.
+	pub fn next_batch(&mut self) {
+		self.batches.push(Infos::default())
+	}
+
+	/// Number of batches.
+	#[inline]
+	pub fn batch_count(&self) -> usize {
+		self.batches.len()
+	}
+
+	/// Number of command buffers.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.buffers.len()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.buffers.is_empty()
+	}
+}
+
+/// Payload of a batched submission.
+///
+/// Owns all the submitted command buffers, and the futures and semaphores waited on.
+pub struct Batched<'k, B> {
+	buffers: Vec<B>,
+	_futures: Vec<Box<dyn future::Futures + Send + Sync + 'k>>,
+	_kept: Vec<Box<dyn Send + Sync + 'k>>
+}
+
+impl<'k, B> Batched<'k, B> {
+	pub fn buffers(&self) -> &[B] {
+		&self.buffers
+	}
+}
+
+unsafe impl<'k, B: command::RecordedBuffer> task::Payload for Batched<'k, B> {}
+
+unsafe impl<'a, 'k, B: command::RecordedBuffer> task::WaitPipelineStages for Batch<'a, 'k, B> {
+	type Output = ();
+	type Error = SubmitError;
+	type Payload = Batched<'k, B>;
+
+	/// Submit all the batches.
+	fn execute<P: future::SignalSemaphores>(
+		mut self,
+		past: Option<&P>,
+		wait_pipeline_stage_mask: Option<&[pipeline::stage::Flags]>,
+		signal_semaphores: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
+		signal_fence: Option<vk::Fence>,
+	) -> Result<((), Self::Payload), SubmitError> {
+		for buffer in &self.buffers {
+			buffer.flush_host_writes()?;
+		}
+
+		if let Some(past) = past {
+			let first = &mut self.batches[0];
+			match wait_pipeline_stage_mask {
+				Some(masks) => first.wait(past, masks),
+				None => first.wait(past, &vec![pipeline::stage::Flags::ALL_COMMANDS; past.semaphores().len()])
+			}
+		}
+
+		if let Some(semaphores) = signal_semaphores {
+			self.current().signal(semaphores, signal_semaphore_values);
+		}
+
+		let timeline_infos: Vec<_> = self.batches.iter().map(Infos::timeline_infos).collect();
+		let infos: Vec<_> = self.batches.iter().zip(&timeline_infos).map(|(batch, timeline_infos)| vk::SubmitInfo {
+			p_next: super::timeline_p_next(timeline_infos),
+
+			wait_semaphore_count: batch.wait_semaphores.len() as u32,
+			p_wait_semaphores: batch.wait_semaphores.as_ptr(),
+			p_wait_dst_stage_mask: batch.wait_pipeline_stage_masks.as_ptr(),
+
+			command_buffer_count: batch.command_buffers.len() as u32,
+			p_command_buffers: batch.command_buffers.as_ptr(),
+
+			signal_semaphore_count: batch.signal_semaphores.len() as u32,
+			p_signal_semaphores: batch.signal_semaphores.as_ptr(),
+			..Default::default()
+		}).collect();
+
+		unsafe {
+			let handle = self.queue.handle.lock();
+			self.queue.device.handle().queue_submit(*handle, &infos, signal_fence.unwrap_or(vk::Fence::null()))?;
+		}
+
+		for future in &self.futures {
+			future.wait_submitted()
+		}
+
+		Ok(((), Batched {
+			buffers: self.buffers,
+			_futures: self.futures,
+			_kept: self.kept
+		}))
+	}
+}
+
+impl<'a, 'k, B: command::RecordedBuffer> task::SignalSemaphore for Batch<'a, 'k, B> {}
+impl<'a, 'k, B: command::RecordedBuffer> task::SignalTimeline for Batch<'a, 'k, B> {}
+impl<'a, 'k, B: command::RecordedBuffer> task::SignalFence for Batch<'a, 'k, B> {}
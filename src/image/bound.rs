@@ -32,6 +32,11 @@ impl<S: Slot> Bound<S> {
 		&self.slot
 	}
 
+	#[inline]
+	pub(crate) fn unbound(&self) -> &Unbound {
+		&self.inner
+	}
+
 	/// Releases the image and returns its memory slot.
 	pub fn unbind(self) -> S {
 		self.slot
@@ -10,11 +10,14 @@ use crate::{
 	Device,
 	Format,
 	DeviceOwned,
+	device::external,
 	sync::SharingQueues,
 	OomError,
 	mem::{
 		Slot,
-		MemoryRequirements
+		MemoryRequirements,
+		memory_requirements::Resource,
+		sparse
 	}
 };
 use super::{
@@ -64,9 +67,17 @@ impl From<vk::Result> for BindError {
 pub struct Unbound {
 	device: Arc<Device>,
 	handle: vk::Image,
+	ty: Type,
+	format: Format,
 	size: (u32, u32, u32),
+	mip_levels: u32,
+	array_layers: u32,
+	samples: vk::SampleCountFlags,
+	tiling: Tiling,
 	usage: Usage,
-	tiling: Tiling
+	sharing_queues: SharingQueues,
+	sparse: Option<sparse::Flags>,
+	external: external::HandleTypes
 }
 
 impl Unbound {
@@ -83,25 +94,115 @@ impl Unbound {
 		sharing_queues: S,
 		initial_layout: Layout
 	) -> Result<Self, OomError> {
-		let sharing_queues = sharing_queues.into();
-		let (sharing_mode, queue_family_index_count, p_queue_family_indices) = sharing_queues.as_vulkan();
+		Self::create(device, ty, format, size, mip_levels, array_layers, samples, tiling, usage, sharing_queues.into(), initial_layout, None, external::HandleTypes::none())
+	}
+
+	/// Creates an image whose memory can be exported to or imported from the given handle types.
+	///
+	/// The handle types should be negotiated with `PhysicalDevice::external_image_properties`.
+	/// This requires the `VK_KHR_external_memory` extension to be enabled.
+	pub fn new_external<S: Into<SharingQueues>>(
+		device: &Arc<Device>,
+		ty: Type,
+		format: Format,
+		size: (u32, u32, u32),
+		mip_levels: u32,
+		array_layers: u32,
+		samples: SampleCount,
+		tiling: Tiling,
+		usage: Usage,
+		sharing_queues: S,
+		initial_layout: Layout,
+		handle_types: external::HandleTypes
+	) -> Result<Self, OomError> {
+		Self::create(device, ty, format, size, mip_levels, array_layers, samples, tiling, usage, sharing_queues.into(), initial_layout, None, handle_types)
+	}
+
+	/// Creates a sparse image.
+	///
+	/// A sparse image cannot be bound with `bind`.
+	/// Its memory is bound tile by tile with `Queue::bind_sparse` once wrapped in a `sparse::Image`.
+	pub fn new_sparse<S: Into<SharingQueues>>(
+		device: &Arc<Device>,
+		ty: Type,
+		format: Format,
+		size: (u32, u32, u32),
+		mip_levels: u32,
+		array_layers: u32,
+		samples: SampleCount,
+		tiling: Tiling,
+		usage: Usage,
+		sharing_queues: S,
+		initial_layout: Layout,
+		flags: sparse::Flags
+	) -> Result<Self, OomError> {
+		Self::create(device, ty, format, size, mip_levels, array_layers, samples, tiling, usage, sharing_queues.into(), initial_layout, Some(flags), external::HandleTypes::none())
+	}
+
+	fn create(
+		device: &Arc<Device>,
+		ty: Type,
+		format: Format,
+		size: (u32, u32, u32),
+		mip_levels: u32,
+		array_layers: u32,
+		samples: SampleCount,
+		tiling: Tiling,
+		usage: Usage,
+		sharing_queues: SharingQueues,
+		initial_layout: Layout,
+		sparse: Option<sparse::Flags>,
+		external: external::HandleTypes
+	) -> Result<Self, OomError> {
+		let mut image = Self {
+			device: device.clone(),
+			handle: vk::Image::null(),
+			ty,
+			format,
+			size,
+			mip_levels,
+			array_layers,
+			samples: samples.into_vulkan(),
+			tiling,
+			usage,
+			sharing_queues,
+			sparse,
+			external
+		};
+
+		image.handle = image.create_handle(initial_layout)?;
+		Ok(image)
+	}
+
+	fn create_handle(&self, initial_layout: Layout) -> Result<vk::Image, OomError> {
+		let (sharing_mode, queue_family_index_count, p_queue_family_indices) = self.sharing_queues.as_vulkan();
 
-		let flags = vk::ImageCreateFlags::empty();
+		let flags = self.sparse.map(|f| f.image_create_flags()).unwrap_or_default();
+
+		let external_infos = vk::ExternalMemoryImageCreateInfo {
+			handle_types: self.external.into_vulkan(),
+			..Default::default()
+		};
 
 		let infos = vk::ImageCreateInfo {
+			p_next: if self.external.is_empty() {
+				std::ptr::null()
+			} else {
+				&external_infos as *const _ as *const std::ffi::c_void
+			},
 			flags,
-			image_type: ty.into_vulkan(),
-			format: format.into_vulkan(),
+			image_type: self.ty.into_vulkan(),
+			format: self.format.into_vulkan(),
 			extent: vk::Extent3D {
-				width: size.0,
-				height: size.1,
-				depth: size.2
+				width: self.size.0,
+				height: self.size.1,
+				depth: self.size.2
 			},
-			mip_levels,
-			array_layers,
-			samples: samples.into_vulkan(),
-			tiling: tiling.into_vulkan(),
-			usage: usage.to_vulkan(),
+			mip_levels: self.mip_levels,
+			array_layers: self.array_layers,
+			samples: self.samples,
+			tiling: self.tiling.into_vulkan(),
+			usage: self.usage.to_vulkan(),
 			sharing_mode,
 			queue_family_index_count,
 			p_queue_family_indices,
@@ -109,16 +210,27 @@ impl Unbound {
 			..Default::default()
 		};
 
-		let handle = unsafe {
-			device.handle().create_image(&infos, None)?
-		};
+		unsafe {
+			Ok(self.device.handle().create_image(&infos, None)?)
+		}
+	}
 
+	/// Creates a new image with the same parameters, in the `Undefined` layout.
+	pub(crate) fn duplicate(&self) -> Result<Self, OomError> {
 		Ok(Self {
-			device: device.clone(),
-			handle,
-			size,
-			usage,
-			tiling
+			device: self.device.clone(),
+			handle: self.create_handle(Layout::Undefined)?,
+			ty: self.ty,
+			format: self.format,
+			size: self.size,
+			mip_levels: self.mip_levels,
+			array_layers: self.array_layers,
+			samples: self.samples,
+			tiling: self.tiling,
+			usage: self.usage,
+			sharing_queues: self.sharing_queues.clone(),
+			sparse: self.sparse,
+			external: self.external
 		})
 	}
 
@@ -132,21 +244,46 @@ impl Unbound {
 		self.size
 	}
 
+	#[inline]
+	pub fn format(&self) -> Format {
+		self.format
+	}
+
+	#[inline]
+	pub fn mip_levels(&self) -> u32 {
+		self.mip_levels
+	}
+
+	#[inline]
+	pub fn array_layers(&self) -> u32 {
+		self.array_layers
+	}
+
 	#[inline]
 	pub fn usage(&self) -> Usage {
 		self.usage
 	}
 
+	/// Handle types the image memory can be exported to or imported from.
+	#[inline]
+	pub fn external_handle_types(&self) -> external::HandleTypes {
+		self.external
+	}
+
+	/// Sparse creation flags, if this is a sparse image.
+	#[inline]
+	pub fn sparse(&self) -> Option<sparse::Flags> {
+		self.sparse
+	}
+
 	#[inline]
 	pub fn memory_requirements(&self) -> MemoryRequirements {
-		unsafe {
-			let mr = self.device.handle.get_image_memory_requirements(self.handle);
-			MemoryRequirements::new(mr, self.tiling.is_linear())
-		}
+		MemoryRequirements::query(&self.device, Resource::Image(self.handle), self.tiling.is_linear())
 	}
 
 	#[inline]
 	pub unsafe fn bind<S: Slot>(self, slot: S) -> Result<Bound<S>, (Self, BindError)> {
+		debug_assert!(self.sparse.is_none(), "sparse images cannot be bound to a single slot");
 		let memory = slot.memory();
 		
 		// We check for correctness in debug mode.
@@ -171,3 +308,11 @@ impl DeviceOwned for Unbound {
 		&self.device
 	}
 }
+
+impl Drop for Unbound {
+	fn drop(&mut self) {
+		unsafe {
+			self.device.handle().destroy_image(self.handle, None)
+		}
+	}
+}
@@ -133,6 +133,25 @@ impl Aspects {
 		Self::new(false, true, true, false)
 	}
 
+	/// Returns the aspects of images of the given format.
+	pub fn of_format(format: Format) -> Self {
+		match format {
+			Format::D16Unorm | Format::X8_D24UnormPack32 | Format::D32Sfloat => Self::depth(),
+			Format::S8Uint => Self::new(false, false, true, false),
+			Format::D16Unorm_S8Uint | Format::D24Unorm_S8Uint | Format::D32Sfloat_S8Uint => Self::depth_stencil(),
+			_ => Self::color()
+		}
+	}
+
+	pub(crate) fn from_vulkan(flags: vk::ImageAspectFlags) -> Self {
+		Self::new(
+			flags.contains(vk::ImageAspectFlags::COLOR),
+			flags.contains(vk::ImageAspectFlags::DEPTH),
+			flags.contains(vk::ImageAspectFlags::STENCIL),
+			flags.contains(vk::ImageAspectFlags::METADATA)
+		)
+	}
+
 	pub(crate) fn into_vulkan(self) -> vk::ImageAspectFlags {
 		let mut flags = vk::ImageAspectFlags::empty();
 
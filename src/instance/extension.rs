@@ -20,4 +20,6 @@ extensions! {
 	ext_swapchain_colorspace: ExtSwapchainColorspace => b"VK_EXT_swapchain_colorspace\0",
 	khr_get_physical_device_properties2: KhrGetPhysicalDeviceproperties2 => b"VK_KHR_get_physical_device_properties2\0",
 	khr_get_surface_capabilities2: KhrGetSurfaceCapabilities2 => b"VK_KHR_get_surface_capabilities2\0",
+	khr_external_memory_capabilities: KhrExternalMemoryCapabilities => b"VK_KHR_external_memory_capabilities\0",
+	khr_device_group_creation: KhrDeviceGroupCreation => b"VK_KHR_device_group_creation\0",
 }
@@ -71,7 +71,9 @@ pub struct Instance {
 	ext_khr_surface: OnceCell<ash::extensions::khr::Surface>,
 	ext_khr_xcb_surface: OnceCell<ash::extensions::khr::XcbSurface>,
 	ext_khr_xlib_surface: OnceCell<ash::extensions::khr::XlibSurface>,
-	ext_khr_wayland_surface: OnceCell<ash::extensions::khr::WaylandSurface>
+	ext_khr_wayland_surface: OnceCell<ash::extensions::khr::WaylandSurface>,
+	ext_khr_get_physical_device_properties2: OnceCell<vk::KhrGetPhysicalDeviceProperties2Fn>,
+	ext_khr_external_memory_capabilities: OnceCell<vk::KhrExternalMemoryCapabilitiesFn>
 }
 
 impl Instance {
@@ -159,7 +161,9 @@ impl Instance {
 				ext_khr_surface: OnceCell::new(),
 				ext_khr_xcb_surface: OnceCell::new(),
 				ext_khr_xlib_surface: OnceCell::new(),
-				ext_khr_wayland_surface: OnceCell::new()
+				ext_khr_wayland_surface: OnceCell::new(),
+				ext_khr_get_physical_device_properties2: OnceCell::new(),
+				ext_khr_external_memory_capabilities: OnceCell::new()
 			};
 
 			Ok(instance)
@@ -234,6 +238,30 @@ impl Instance {
 			}
 		})
 	}
+
+	pub fn ext_khr_get_physical_device_properties2(&self) -> Result<&vk::KhrGetPhysicalDeviceProperties2Fn, MissingExtensionError> {
+		self.ext_khr_get_physical_device_properties2.get_or_try_init(|| {
+			if self.loaded_extensions.khr_get_physical_device_properties2 {
+				Ok(vk::KhrGetPhysicalDeviceProperties2Fn::load(|name| unsafe {
+					std::mem::transmute(self.entry.handle.get_instance_proc_addr(self.handle.handle(), name.as_ptr()))
+				}))
+			} else {
+				Err(MissingExtensionError(Extension::KhrGetPhysicalDeviceproperties2))
+			}
+		})
+	}
+
+	pub fn ext_khr_external_memory_capabilities(&self) -> Result<&vk::KhrExternalMemoryCapabilitiesFn, MissingExtensionError> {
+		self.ext_khr_external_memory_capabilities.get_or_try_init(|| {
+			if self.loaded_extensions.khr_external_memory_capabilities {
+				Ok(vk::KhrExternalMemoryCapabilitiesFn::load(|name| unsafe {
+					std::mem::transmute(self.entry.handle.get_instance_proc_addr(self.handle.handle(), name.as_ptr()))
+				}))
+			} else {
+				Err(MissingExtensionError(Extension::KhrExternalMemoryCapabilities))
+			}
+		})
+	}
 }
 
 impl Drop for Instance {
@@ -1,6 +1,9 @@
 use ash::vk;
 use crate::mem::MemoryRequirements;
-use super::PhysicalDevice;
+use super::{
+	PhysicalDevice,
+	MemoryHeap
+};
 
 pub struct MemoryType<'a> {
 	physical_device: PhysicalDevice<'a>,
@@ -23,11 +26,29 @@ impl<'a> MemoryType<'a> {
 		self.index
 	}
 
+	/// Returns the index of the heap this memory type corresponds to.
+	#[inline]
+	pub fn heap_index(&self) -> u32 {
+		self.memory_type.heap_index
+	}
+
+	/// Returns the heap this memory type corresponds to.
+	#[inline]
+	pub fn heap(&self) -> MemoryHeap<'a> {
+		MemoryHeap::new(self.physical_device, self.memory_type.heap_index)
+	}
+
 	#[inline]
 	pub fn size(&self) -> u64 {
 		self.physical_device.p.memory_properties.memory_heaps[self.memory_type.heap_index as usize].size
 	}
 
+	/// Returns the property flags of the memory type.
+	#[inline]
+	pub(crate) fn property_flags(&self) -> vk::MemoryPropertyFlags {
+		self.memory_type.property_flags
+	}
+
 	#[inline]
 	pub fn satisfies(&self, reqs: &MemoryRequirements) -> bool {
 		reqs.contains_memory_type_index(self.index)
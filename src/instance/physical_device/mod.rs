@@ -11,7 +11,16 @@ use ash::{
 	version::InstanceV1_0
 };
 
-use crate::device;
+use crate::{
+	OomError,
+	Format,
+	device::{
+		self,
+		external
+	},
+	image,
+	mem::buffer
+};
 use super::{
 	Instance,
 	PhysicalDeviceInfo
@@ -19,10 +28,12 @@ use super::{
 
 mod limits;
 mod memory_type;
+mod memory_heap;
 mod queue_family;
 
 pub use limits::Limits;
 pub use memory_type::MemoryType;
+pub use memory_heap::MemoryHeap;
 pub use queue_family::QueueFamily;
 
 #[derive(Clone, Copy)]
@@ -107,9 +118,119 @@ impl<'a> PhysicalDevice<'a> {
 		(0u32..len).into_iter().map(move |i| MemoryType::new(this, i))
 	}
 
+	#[inline]
+	pub fn memory_heap_count(&self) -> u32 {
+		self.p.memory_properties.memory_heap_count
+	}
+
+	#[inline]
+	pub fn memory_heap(&self, index: u32) -> Option<MemoryHeap<'a>> {
+		if index < self.memory_heap_count() {
+			Some(MemoryHeap::new(*self, index))
+		} else {
+			None
+		}
+	}
+
+	#[inline]
+	pub fn memory_heaps(&self) -> impl 'a + Iterator<Item=MemoryHeap<'a>> {
+		let this = *self;
+		let len = self.memory_heap_count();
+		(0u32..len).into_iter().map(move |i| MemoryHeap::new(this, i))
+	}
+
+	/// Checks if the device has a unified memory architecture,
+	/// where every memory heap is located on the device.
+	///
+	/// This is typically the case of integrated GPUs.
+	#[inline]
+	pub fn is_uma(&self) -> bool {
+		self.memory_heaps().all(|heap| heap.is_device_local())
+	}
+
 	pub fn limits(&self) -> Limits<'a> {
 		Limits::from_vk_limits(&self.p.properties.limits)
 	}
+
+	/// Returns the supported features of the `VK_KHR_buffer_device_address` extension.
+	///
+	/// This requires the `VK_KHR_get_physical_device_properties2` instance extension to be enabled.
+	pub fn buffer_device_address_features(&self) -> Result<device::BufferDeviceAddressFeatures, super::MissingExtensionError> {
+		let ext = self.instance.ext_khr_get_physical_device_properties2()?;
+
+		let mut buffer_device_address = vk::PhysicalDeviceBufferDeviceAddressFeatures::default();
+		let mut features = vk::PhysicalDeviceFeatures2 {
+			p_next: &mut buffer_device_address as *mut _ as *mut std::ffi::c_void,
+			..Default::default()
+		};
+
+		unsafe {
+			ext.get_physical_device_features2_khr(self.handle(), &mut features);
+		}
+
+		Ok(device::BufferDeviceAddressFeatures::from_vulkan(&buffer_device_address))
+	}
+
+	/// External memory capabilities of buffers with the given usage, for the given handle type.
+	///
+	/// This requires the `VK_KHR_external_memory_capabilities` instance extension to be enabled.
+	pub fn external_buffer_properties<U: Into<buffer::Usages>>(&self, usage: U, handle_type: external::HandleType) -> Result<external::Properties, external::QueryError> {
+		let ext = self.instance.ext_khr_external_memory_capabilities()?;
+
+		let infos = vk::PhysicalDeviceExternalBufferInfo {
+			usage: usage.into().into_vulkan(),
+			handle_type: handle_type.into_vulkan(),
+			..Default::default()
+		};
+
+		let mut properties = vk::ExternalBufferProperties::default();
+		unsafe {
+			ext.get_physical_device_external_buffer_properties_khr(self.handle(), &infos, &mut properties);
+		}
+
+		Ok(external::Properties::new(properties.external_memory_properties))
+	}
+
+	/// External memory capabilities of images with the given parameters, for the given handle type.
+	///
+	/// Returns `None` if such images are not supported with this handle type.
+	///
+	/// This requires the `VK_KHR_external_memory_capabilities` and
+	/// `VK_KHR_get_physical_device_properties2` instance extensions to be enabled.
+	pub fn external_image_properties(&self, ty: image::Type, format: Format, tiling: image::Tiling, usage: image::Usage, handle_type: external::HandleType) -> Result<Option<external::Properties>, external::QueryError> {
+		self.instance.ext_khr_external_memory_capabilities()?;
+		let ext = self.instance.ext_khr_get_physical_device_properties2()?;
+
+		let external_infos = vk::PhysicalDeviceExternalImageFormatInfo {
+			handle_type: handle_type.into_vulkan(),
+			..Default::default()
+		};
+
+		let infos = vk::PhysicalDeviceImageFormatInfo2 {
+			p_next: &external_infos as *const _ as *const std::ffi::c_void,
+			format: format.into_vulkan(),
+			ty: ty.into_vulkan(),
+			tiling: tiling.into_vulkan(),
+			usage: usage.to_vulkan(),
+			..Default::default()
+		};
+
+		let mut external_properties = vk::ExternalImageFormatProperties::default();
+		let mut properties = vk::ImageFormatProperties2 {
+			p_next: &mut external_properties as *mut _ as *mut std::ffi::c_void,
+			..Default::default()
+		};
+
+		unsafe {
+			match ext.get_physical_device_image_format_properties2_khr(self.handle(), &infos, &mut properties) {
+				vk::Result::SUCCESS => Ok(Some(external::Properties::new(external_properties.external_memory_properties))),
+				vk::Result::ERROR_FORMAT_NOT_SUPPORTED => Ok(None),
+				vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(external::QueryError::OutOfMemory(OomError::Host)),
+				vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(external::QueryError::OutOfMemory(OomError::Device)),
+				_ => unreachable!()
+			}
+		}
+	}
 }
 
 impl<'a> PartialEq for PhysicalDevice<'a> {
@@ -0,0 +1,427 @@
+use std::{
+	ffi::c_void,
+	ops::Range,
+	sync::{
+		Arc,
+		atomic::{
+			AtomicU64,
+			Ordering
+		}
+	}
+};
+use parking_lot::{
+	Mutex,
+	RwLock
+};
+use crate::{
+	Device,
+	DeviceOwned,
+	device
+};
+use super::{
+	Error,
+	MemoryHook,
+	MemoryRequirements,
+	HostVisible,
+	HostWrites,
+	stats
+};
+
+/// Memory limit.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limit {
+	/// Number of bytes above which allocations fail with `Error::OutOfMemory`.
+	pub hard: Option<u64>,
+
+	/// Number of bytes above which the soft limit callback is called.
+	pub soft: Option<u64>
+}
+
+impl Limit {
+	/// Hard limit only.
+	pub fn hard(bytes: u64) -> Self {
+		Self {
+			hard: Some(bytes),
+			soft: None
+		}
+	}
+
+	/// Soft limit only.
+	pub fn soft(bytes: u64) -> Self {
+		Self {
+			hard: None,
+			soft: Some(bytes)
+		}
+	}
+}
+
+/// Information passed to the soft limit callback.
+#[derive(Clone, Copy, Debug)]
+pub struct SoftLimitReached {
+	/// Heap whose soft limit has been reached, or `None` for the total limit.
+	pub heap: Option<u32>,
+
+	/// Number of bytes allocated after the allocation that reached the limit.
+	pub usage: u64,
+
+	/// Soft limit.
+	pub limit: u64
+}
+
+/// Limits of a `Bounded` allocator.
+struct Limits {
+	total: Limit,
+	heaps: Vec<Limit>,
+	callback: Option<Arc<dyn Send + Sync + Fn(SoftLimitReached)>>
+}
+
+/// Number of bytes allocated through a `Bounded` allocator, and its limits.
+///
+/// This is the memory hook installed in the underlying allocator, if supported.
+struct Accounting {
+	total: AtomicU64,
+	heaps: Vec<AtomicU64>,
+	limits: RwLock<Limits>,
+
+	/// Soft limits crossed since the last call to `notify`.
+	///
+	/// The callback is not called by `reserve` directly since it may run
+	/// while the underlying allocator holds its own locks.
+	crossed: Mutex<Vec<SoftLimitReached>>
+}
+
+impl Accounting {
+	/// Accounts for `size` more bytes in the given heap.
+	///
+	/// Fails with `Error::OutOfMemory` if it exceeds a hard limit,
+	/// and records the crossing if it crosses a soft limit.
+	/// The soft limit callback is called later by `notify`.
+	fn reserve(&self, heap: u32, size: u64) -> Result<(), Error> {
+		let limits = self.limits.read();
+		let total = reserve(&self.total, limits.total.hard, size).ok_or(Error::OutOfMemory)?;
+		let heap_usage = match reserve(&self.heaps[heap as usize], limits.heaps[heap as usize].hard, size) {
+			Some(heap_usage) => heap_usage,
+			None => {
+				self.total.fetch_sub(size, Ordering::Relaxed);
+				return Err(Error::OutOfMemory)
+			}
+		};
+
+		if limits.callback.is_some() {
+			let mut crossed = self.crossed.lock();
+
+			if let Some(soft) = limits.total.soft {
+				if total <= soft && total + size > soft {
+					crossed.push(SoftLimitReached {
+						heap: None,
+						usage: total + size,
+						limit: soft
+					})
+				}
+			}
+
+			if let Some(soft) = limits.heaps[heap as usize].soft {
+				if heap_usage <= soft && heap_usage + size > soft {
+					crossed.push(SoftLimitReached {
+						heap: Some(heap),
+						usage: heap_usage + size,
+						limit: soft
+					})
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	fn release(&self, heap: u32, size: u64) {
+		self.total.fetch_sub(size, Ordering::Relaxed);
+		self.heaps[heap as usize].fetch_sub(size, Ordering::Relaxed);
+	}
+
+	/// Calls the soft limit callback for each soft limit crossed since the last call.
+	///
+	/// No lock is held while the callback runs,
+	/// so that it can free memory of the allocator.
+	fn notify(&self) {
+		let crossed = std::mem::take(&mut *self.crossed.lock());
+		if !crossed.is_empty() {
+			let callback = self.limits.read().callback.clone();
+			if let Some(callback) = callback {
+				for reached in crossed {
+					callback(reached)
+				}
+			}
+		}
+	}
+}
+
+impl MemoryHook for Accounting {
+	fn allocate(&self, heap: u32, size: u64) -> Result<(), Error> {
+		self.reserve(heap, size)
+	}
+
+	fn free(&self, heap: u32, size: u64) {
+		self.release(heap, size)
+	}
+}
+
+/// Adds `size` to the counter if it does not exceed the hard limit.
+///
+/// Returns the previous value of the counter in case of success.
+fn reserve(counter: &AtomicU64, hard: Option<u64>, size: u64) -> Option<u64> {
+	counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+		match hard {
+			Some(hard) if current + size > hard => None,
+			_ => Some(current + size)
+		}
+	}).ok()
+}
+
+/// Memory slot allocated by a `Bounded` allocator.
+pub struct Slot<S: super::Slot> {
+	inner: S,
+	heap: u32,
+
+	/// Accounting of the slot, if the underlying allocator does not report its memory blocks.
+	accounting: Option<Arc<Accounting>>
+}
+
+impl<S: super::Slot> Slot<S> {
+	/// Returns the underlying slot, without releasing its memory usage.
+	fn into_raw_parts(self) -> (S, u32, Option<Arc<Accounting>>) {
+		let this = std::mem::ManuallyDrop::new(self);
+		unsafe {
+			(std::ptr::read(&this.inner), this.heap, std::ptr::read(&this.accounting))
+		}
+	}
+}
+
+unsafe impl<S: super::Slot> super::Slot for Slot<S> {
+	#[inline]
+	fn memory(&self) -> &device::Memory {
+		self.inner.memory()
+	}
+
+	#[inline]
+	fn offset(&self) -> u64 {
+		self.inner.offset()
+	}
+
+	#[inline]
+	fn size(&self) -> u64 {
+		self.inner.size()
+	}
+
+	#[inline]
+	fn ptr(&self) -> Option<*mut c_void> {
+		self.inner.ptr()
+	}
+
+	#[inline]
+	fn mark_dirty(&self, range: Range<u64>) {
+		self.inner.mark_dirty(range)
+	}
+
+	#[inline]
+	fn host_writes(&self) -> Option<&Arc<HostWrites>> {
+		self.inner.host_writes()
+	}
+}
+
+impl<S: super::Slot> Drop for Slot<S> {
+	fn drop(&mut self) {
+		if let Some(accounting) = &self.accounting {
+			accounting.release(self.heap, self.inner.size())
+		}
+	}
+}
+
+/// Allocator with logical memory limits.
+///
+/// Wraps an allocator and enforces a total and per-heap number of allocated bytes.
+/// When the underlying allocator supports memory hooks (such as `Unbounded`),
+/// the limits apply to the device memory blocks it allocates,
+/// and a block allocation that would exceed a hard limit fails with `Error::OutOfMemory`
+/// before reaching the driver.
+/// Otherwise the limits apply to the allocated slots.
+/// When an allocation makes the usage cross a soft limit,
+/// the soft limit callback is called.
+pub struct Bounded<A: super::Allocator> {
+	/// Underlying allocator.
+	allocator: A,
+
+	accounting: Arc<Accounting>,
+
+	/// Is the memory accounted through the memory hook of the underlying allocator.
+	hooked: bool
+}
+
+impl<A: super::Allocator> Bounded<A> {
+	/// Creates a new bounded allocator, without any limit.
+	///
+	/// This installs the memory hook of the underlying allocator, if supported.
+	pub fn new(allocator: A) -> Self {
+		let heap_count = allocator.device().physical_device().memory_heap_count() as usize;
+
+		let mut heaps = Vec::new();
+		heaps.resize_with(heap_count, || AtomicU64::new(0));
+
+		let accounting = Arc::new(Accounting {
+			total: AtomicU64::new(0),
+			heaps,
+			limits: RwLock::new(Limits {
+				total: Limit::default(),
+				heaps: vec![Limit::default(); heap_count],
+				callback: None
+			}),
+			crossed: Mutex::new(Vec::new())
+		});
+
+		let hooked = allocator.set_memory_hook(accounting.clone());
+
+		Self {
+			allocator,
+			accounting,
+			hooked
+		}
+	}
+
+	/// Sets the limit on the total number of allocated bytes.
+	pub fn set_total_limit(&mut self, limit: Limit) {
+		self.accounting.limits.write().total = limit
+	}
+
+	/// Sets the limit on the number of bytes allocated in the given heap.
+	///
+	/// ## Panics
+	///
+	/// This function panics if there is no heap with the given index.
+	pub fn set_heap_limit(&mut self, heap: u32, limit: Limit) {
+		self.accounting.limits.write().heaps[heap as usize] = limit
+	}
+
+	/// Sets the function called when a soft limit is reached.
+	///
+	/// The function is called once the allocation crossing the limit returns,
+	/// without any lock of the allocator held. It may free memory of the allocator.
+	pub fn set_soft_limit_callback<F: 'static + Send + Sync + Fn(SoftLimitReached)>(&mut self, f: F) {
+		self.accounting.limits.write().callback = Some(Arc::new(f))
+	}
+
+	/// Returns the total number of bytes currently allocated.
+	pub fn usage(&self) -> u64 {
+		self.accounting.total.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of bytes currently allocated in the given heap.
+	pub fn heap_usage(&self, heap: u32) -> u64 {
+		self.accounting.heaps[heap as usize].load(Ordering::Relaxed)
+	}
+
+	/// Removes from the memory requirements the memory types
+	/// whose heap cannot hold `size` more bytes.
+	///
+	/// When the memory is accounted through the memory hook,
+	/// the usage counts whole memory blocks and the hard limits are enforced
+	/// when a block is allocated, so the memory requirements are left untouched.
+	fn filtered_memory_requirements(&self, memory_requirements: MemoryRequirements) -> Result<MemoryRequirements, Error> {
+		if self.hooked {
+			return Ok(memory_requirements)
+		}
+
+		let size = memory_requirements.size();
+		let limits = self.accounting.limits.read();
+
+		if let Some(hard) = limits.total.hard {
+			if self.usage() + size > hard {
+				return Err(Error::OutOfMemory)
+			}
+		}
+
+		let memory_requirements = memory_requirements.filter_memory_types(self.device().physical_device(), |memory_type| {
+			let heap = memory_type.heap_index();
+			match limits.heaps[heap as usize].hard {
+				Some(hard) => self.heap_usage(heap) + size <= hard,
+				None => true
+			}
+		});
+
+		if memory_requirements.memory_type_bits() == 0 {
+			Err(Error::OutOfMemory)
+		} else {
+			Ok(memory_requirements)
+		}
+	}
+
+	/// Accounts for a newly allocated slot.
+	///
+	/// If the memory is accounted through the memory hook, the slot is not accounted for.
+	fn register(&self, slot: A::Slot) -> Result<Slot<A::Slot>, Error> {
+		use super::Slot as _;
+		let heap = slot.memory().memory_type().heap_index();
+
+		let accounting = if self.hooked {
+			None
+		} else {
+			self.accounting.reserve(heap, slot.size())?;
+			Some(self.accounting.clone())
+		};
+
+		Ok(Slot {
+			inner: slot,
+			heap,
+			accounting
+		})
+	}
+}
+
+unsafe impl<A: super::Allocator> super::Allocator for Bounded<A> {
+	type Slot = Slot<A::Slot>;
+
+	fn prepare(&self, memory_requirements: MemoryRequirements) {
+		if let Ok(memory_requirements) = self.filtered_memory_requirements(memory_requirements) {
+			self.allocator.prepare(memory_requirements)
+		}
+	}
+
+	fn allocate(&self, memory_requirements: MemoryRequirements) -> Result<Self::Slot, Error> {
+		let memory_requirements = self.filtered_memory_requirements(memory_requirements)?;
+		let slot = self.allocator.allocate(memory_requirements).and_then(|slot| self.register(slot));
+		self.accounting.notify();
+		slot
+	}
+
+	fn reallocate(&self, slot: HostVisible<Self::Slot>, memory_requirements: MemoryRequirements) -> Result<HostVisible<Self::Slot>, Error> {
+		use super::Slot as _;
+		let (slot, dirty) = slot.into_raw_parts();
+		let (inner, heap, accounting) = slot.into_raw_parts();
+		if let Some(accounting) = accounting {
+			accounting.release(heap, inner.size());
+		}
+
+		let memory_requirements = self.filtered_memory_requirements(memory_requirements)?;
+		let slot = self.allocator.reallocate(HostVisible::from_raw_parts(inner, dirty), memory_requirements).and_then(|slot| {
+			let (inner, dirty) = slot.into_raw_parts();
+			Ok(HostVisible::from_raw_parts(self.register(inner)?, dirty))
+		});
+		self.accounting.notify();
+		slot
+	}
+}
+
+impl<A: super::Allocator + stats::Inspect> stats::Inspect for Bounded<A> {
+	fn dump(&self) -> stats::Dump {
+		self.allocator.dump()
+	}
+
+	fn statistics(&self) -> stats::Statistics {
+		self.allocator.statistics()
+	}
+}
+
+impl<A: super::Allocator> DeviceOwned for Bounded<A> {
+	fn device(&self) -> &Arc<Device> {
+		self.allocator.device()
+	}
+}
@@ -1,7 +1,7 @@
 use ash::vk;
 use crate::resource;
 
-mod usage;
+pub mod usage;
 pub mod sub;
 
 mod unbound;
@@ -9,16 +9,29 @@ mod bound;
 mod typed;
 // mod index;
 pub mod vec;
+pub mod device_vec;
 pub mod array;
+pub mod readback;
 
-pub use usage::*;
+pub use usage::{
+	Usage,
+	Usages
+};
 
 pub use unbound::*;
 pub use bound::*;
 pub use typed::*;
 // pub use index::*;
 pub use vec::Vec;
-pub use array::Array;
+pub use device_vec::DeviceVec;
+pub use readback::{
+	readback,
+	Readback
+};
+pub use array::{
+	Array,
+	SyncArray
+};
 
 /// Raw buffer handle.
 pub type Handle = vk::Buffer;
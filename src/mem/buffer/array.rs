@@ -1,33 +1,62 @@
 use std::{
 	cell::Cell,
 	ops::{
-		Deref
+		Deref,
+		Range,
+		RangeBounds
 	},
 	rc::Rc,
 	sync::{
 		Arc,
 		Mutex,
-		MutexGuard
+		MutexGuard,
+		atomic::{
+			AtomicIsize,
+			Ordering
+		}
 	}
 };
 use crate::{
 	resource,
-	mem::Slot
+	mem::{
+		Slot,
+		HostWrites
+	}
 };
 use super::{
 	Handle,
 	Typed,
+	Usages,
+	usage,
 	sub
 };
 
 pub struct Busy;
 
+/// Element borrow tracker.
+///
+/// ## Safety
+///
+/// `read` must only succeed if no write lock is held,
+/// and `write` must only succeed if no other lock is held.
+pub unsafe trait ElementLock: Default {
+	/// Tries to acquire a read lock.
+	fn read(&self) -> bool;
+
+	/// Tries to acquire a write lock.
+	fn write(&self) -> bool;
+
+	/// Releases the lock held by the caller.
+	fn release(&self);
+}
+
+/// Single-threaded element lock.
 #[derive(Default)]
-struct Lock {
+pub struct Lock {
 	count: Cell<isize>
 }
 
-impl Lock {
+unsafe impl ElementLock for Lock {
 	fn read(&self) -> bool {
 		let current = self.count.get();
 
@@ -59,16 +88,59 @@ impl Lock {
 	}
 }
 
+/// Thread-safe element lock.
+#[derive(Default)]
+pub struct AtomicLock {
+	/// Number of readers, or -1 if the element is borrowed mutably.
+	count: AtomicIsize
+}
+
+unsafe impl ElementLock for AtomicLock {
+	fn read(&self) -> bool {
+		let mut current = self.count.load(Ordering::Relaxed);
+
+		while current >= 0 {
+			match self.count.compare_exchange_weak(current, current + 1, Ordering::Acquire, Ordering::Relaxed) {
+				Ok(_) => return true,
+				Err(c) => current = c
+			}
+		}
+
+		false
+	}
+
+	fn write(&self) -> bool {
+		self.count.compare_exchange(0, -1, Ordering::Acquire, Ordering::Relaxed).is_ok()
+	}
+
+	fn release(&self) {
+		// While a write lock is held, no other thread can modify the counter.
+		if self.count.load(Ordering::Relaxed) < 0 {
+			self.count.store(0, Ordering::Release)
+		} else {
+			self.count.fetch_sub(1, Ordering::Release);
+		}
+	}
+}
+
 /// Array buffer where each element can be borrowed independently.
-pub struct Array<T, S> {
-	inner: Typed<T, S>,
-	locks: Vec<Lock>
+///
+/// The element lock type `L` defaults to a single-threaded lock.
+/// Use `SyncArray` to borrow elements from multiple threads.
+pub struct Array<T, S, L: ElementLock = Lock, U: usage::Set = Usages> {
+	inner: Typed<T, S, U>,
+	locks: Vec<L>
 }
 
-impl<T, S> Array<T, S> {
-	pub fn new(buffer: Typed<T, S>) -> Self {
+/// Array buffer whose elements can be borrowed independently from multiple threads.
+///
+/// Elements are borrowed through a shared `Arc<SyncArray<T, S>>`.
+pub type SyncArray<T, S, U = Usages> = Array<T, S, AtomicLock, U>;
+
+impl<T, S, L: ElementLock, U: usage::Set> Array<T, S, L, U> {
+	pub fn new(buffer: Typed<T, S, U>) -> Self {
 		let mut locks = Vec::new();
-		locks.resize_with(buffer.len() as usize, || Lock::default());
+		locks.resize_with(buffer.len() as usize, L::default);
 
 		Array {
 			inner: buffer,
@@ -79,13 +151,43 @@ impl<T, S> Array<T, S> {
 	fn offset_of(&self, index: u32) -> u64 {
 		(index as usize * std::mem::size_of::<T>()) as u64
 	}
+
+	/// Locks every element of the given range, or none of them.
+	fn lock_range(&self, range: Range<u64>, write: bool) -> bool {
+		for i in range.clone() {
+			let lock = &self.locks[i as usize];
+			let locked = if write {
+				lock.write()
+			} else {
+				lock.read()
+			};
+
+			if !locked {
+				for j in range.start..i {
+					self.locks[j as usize].release()
+				}
+
+				return false
+			}
+		}
+
+		true
+	}
+
+	fn release_range(&self, range: Range<u64>) {
+		for i in range {
+			self.locks[i as usize].release()
+		}
+	}
 }
 
 pub trait Reference: Sized {
 	type Item;
 	type Slot: Slot;
+	type Lock: ElementLock;
+	type Usage: usage::Set;
 
-	type ReadGuard<'a>: Deref<Target=Array<Self::Item, Self::Slot>> where Self: 'a;
+	type ReadGuard<'a>: Deref<Target=Array<Self::Item, Self::Slot, Self::Lock, Self::Usage>> where Self: 'a;
 
 	fn read<'a>(&'a self) -> Self::ReadGuard<'a>;
 
@@ -138,48 +240,131 @@ pub trait Reference: Sized {
 			Err(Busy)
 		}
 	}
+
+	/// Borrows the given range of elements.
+	///
+	/// Fails if any element of the range is mutably borrowed.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	fn try_slice<B: RangeBounds<u64>>(self, range: B) -> Result<ReadSlice<Self>, Busy> {
+		let (can_read, range, handle, ptr) = {
+			use resource::Reference;
+			let guard = self.read();
+			let range = sub::resolve_range(range, guard.inner.len());
+			let can_read = guard.lock_range(range.clone(), false);
+			let handle = guard.inner.handle();
+			let ptr = guard.inner.memory_slot().ptr().map(|ptr| unsafe { (ptr as *const Self::Item).offset(range.start as isize) });
+
+			(can_read, range, handle, ptr)
+		};
+
+		if can_read {
+			Ok(ReadSlice {
+				array: self,
+				range,
+				handle,
+				ptr
+			})
+		} else {
+			Err(Busy)
+		}
+	}
+
+	/// Mutably borrows the given range of elements.
+	///
+	/// Fails if any element of the range is borrowed.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	fn try_slice_mut<B: RangeBounds<u64>>(self, range: B) -> Result<WriteSlice<Self>, Busy> {
+		let (can_write, range, handle, ptr) = {
+			use resource::Reference;
+			let guard = self.read();
+			let range = sub::resolve_range(range, guard.inner.len());
+			let can_write = guard.lock_range(range.clone(), true);
+			let handle = guard.inner.handle();
+			let ptr = guard.inner.memory_slot().ptr().map(|ptr| unsafe { (ptr as *mut Self::Item).offset(range.start as isize) });
+
+			(can_write, range, handle, ptr)
+		};
+
+		if can_write {
+			Ok(WriteSlice {
+				array: self,
+				range,
+				handle,
+				ptr
+			})
+		} else {
+			Err(Busy)
+		}
+	}
 }
 
-impl<'a, T, S: Slot> Reference for &'a Array<T, S> {
+impl<'a, T, S: Slot, L: ElementLock, U: usage::Set> Reference for &'a Array<T, S, L, U> {
 	type Item = T;
 	type Slot = S;
-	type ReadGuard<'b> where Self: 'b = &'a Array<T, S>;
+	type Lock = L;
+	type Usage = U;
+	type ReadGuard<'b> where Self: 'b = &'a Array<T, S, L, U>;
 
 	fn read<'b>(&'b self) -> Self::ReadGuard<'b> {
 		self
 	}
 }
 
-impl<T: 'static, S: Slot + 'static> Reference for Rc<Array<T, S>> {
+impl<T: 'static, S: Slot + 'static, U: usage::Set> Reference for Rc<Array<T, S, Lock, U>> {
 	type Item = T;
 	type Slot = S;
-	type ReadGuard<'b> = &'b Array<T, S>;
+	type Lock = Lock;
+	type Usage = U;
+	type ReadGuard<'b> = &'b Array<T, S, Lock, U>;
 
 	fn read<'b>(&'b self) -> Self::ReadGuard<'b> {
 		self
 	}
 }
 
-impl<T: 'static, S: Slot + 'static> Reference for Arc<Mutex<Array<T, S>>> {
+impl<T: 'static, S: Slot + 'static, U: usage::Set> Reference for Arc<Mutex<Array<T, S, Lock, U>>> {
 	type Item = T;
 	type Slot = S;
-	type ReadGuard<'b> = MutexGuard<'b, Array<T, S>>;
+	type Lock = Lock;
+	type Usage = U;
+	type ReadGuard<'b> = MutexGuard<'b, Array<T, S, Lock, U>>;
 
 	fn read<'b>(&'b self) -> Self::ReadGuard<'b> {
 		self.lock().expect("unable to lock array")
 	}
 }
 
-impl<T: 'static, S: Slot + 'static> Reference for Arc<parking_lot::Mutex<Array<T, S>>> {
+impl<T: 'static, S: Slot + 'static, U: usage::Set> Reference for Arc<parking_lot::Mutex<Array<T, S, Lock, U>>> {
 	type Item = T;
 	type Slot = S;
-	type ReadGuard<'b> = parking_lot::MutexGuard<'b, Array<T, S>>;
+	type Lock = Lock;
+	type Usage = U;
+	type ReadGuard<'b> = parking_lot::MutexGuard<'b, Array<T, S, Lock, U>>;
 
 	fn read<'b>(&'b self) -> Self::ReadGuard<'b> {
 		self.lock()
 	}
 }
 
+/// Elements of a shared `SyncArray` can be borrowed concurrently without locking the whole array.
+impl<T: 'static, S: Slot + 'static, U: usage::Set> Reference for Arc<SyncArray<T, S, U>> {
+	type Item = T;
+	type Slot = S;
+	type Lock = AtomicLock;
+	type Usage = U;
+	type ReadGuard<'b> = &'b SyncArray<T, S, U>;
+
+	fn read<'b>(&'b self) -> Self::ReadGuard<'b> {
+		self
+	}
+}
+
 pub struct Read<R: Reference> {
 	/// Array reference.
 	array: R,
@@ -219,6 +404,8 @@ unsafe impl<R: Reference> resource::Reference for Read<R> {
 }
 
 unsafe impl<R: Reference> sub::Read for Read<R> {
+	type Usage = R::Usage;
+
 	fn byte_offset(&self) -> u64 {
 		self.offset
 	}
@@ -226,8 +413,15 @@ unsafe impl<R: Reference> sub::Read for Read<R> {
 	fn byte_len(&self) -> u64 {
 		std::mem::size_of::<R::Item>() as u64
 	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		self.array.read().inner.memory_slot().host_writes().cloned()
+	}
 }
 
+// Shared references to the element may be sent to other threads.
+unsafe impl<R: Reference + Send> Send for Read<R> where R::Item: Sync {}
+
 impl<R: Reference> Drop for Read<R> {
 	fn drop(&mut self) {
 		let guard = self.array.read();
@@ -258,7 +452,9 @@ impl<R: Reference> Write<R> {
 	}
 
 	pub fn get_mut(&mut self) -> Option<&mut R::Item> {
-		self.ptr.map(|ptr| unsafe { &mut *ptr })
+		let ptr = self.ptr?;
+		self.array.read().inner.memory_slot().mark_dirty(self.offset..(self.offset + std::mem::size_of::<R::Item>() as u64));
+		Some(unsafe { &mut *ptr })
 	}
 }
 
@@ -278,6 +474,8 @@ unsafe impl<R: Reference> resource::Reference for Write<R> {
 }
 
 unsafe impl<R: Reference> sub::Read for Write<R> {
+	type Usage = R::Usage;
+
 	fn byte_offset(&self) -> u64 {
 		self.offset
 	}
@@ -285,13 +483,154 @@ unsafe impl<R: Reference> sub::Read for Write<R> {
 	fn byte_len(&self) -> u64 {
 		std::mem::size_of::<R::Item>() as u64
 	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		self.array.read().inner.memory_slot().host_writes().cloned()
+	}
 }
 
 unsafe impl<R: Reference> sub::Write for Write<R> {}
 
+// The element is borrowed mutably: it may be sent to another thread.
+unsafe impl<R: Reference + Send> Send for Write<R> where R::Item: Send {}
+
 impl<R: Reference> Drop for Write<R> {
 	fn drop(&mut self) {
 		let guard = self.array.read();
 		guard.locks[self.index as usize].release()
 	}
-}
\ No newline at end of file
+}
+
+/// Borrowed range of array elements.
+pub struct ReadSlice<R: Reference> {
+	/// Array reference.
+	array: R,
+
+	/// Range of elements in the array.
+	range: Range<u64>,
+
+	/// Raw buffer handle.
+	handle: Handle,
+
+	/// Pointer to the first element (if the inner buffer is host accessible).
+	ptr: Option<*const R::Item>
+}
+
+impl<R: Reference> ReadSlice<R> {
+	pub fn get(&self) -> Option<&[R::Item]> {
+		self.ptr.map(|ptr| unsafe { std::slice::from_raw_parts(ptr, (self.range.end - self.range.start) as usize) })
+	}
+}
+
+unsafe impl<R: Reference> resource::Reference for ReadSlice<R> {
+	type Handle = Handle;
+
+	fn handle(&self) -> Handle {
+		self.handle
+	}
+}
+
+unsafe impl<R: Reference> sub::Read for ReadSlice<R> {
+	type Usage = R::Usage;
+
+	fn byte_offset(&self) -> u64 {
+		self.range.start * std::mem::size_of::<R::Item>() as u64
+	}
+
+	fn byte_len(&self) -> u64 {
+		(self.range.end - self.range.start) * std::mem::size_of::<R::Item>() as u64
+	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		self.array.read().inner.memory_slot().host_writes().cloned()
+	}
+}
+
+unsafe impl<R: Reference> sub::TypedRead for ReadSlice<R> {
+	type Item = R::Item;
+
+	fn len(&self) -> u64 {
+		self.range.end - self.range.start
+	}
+}
+
+unsafe impl<R: Reference + Send> Send for ReadSlice<R> where R::Item: Sync {}
+
+impl<R: Reference> Drop for ReadSlice<R> {
+	fn drop(&mut self) {
+		self.array.read().release_range(self.range.clone())
+	}
+}
+
+/// Mutably borrowed range of array elements.
+pub struct WriteSlice<R: Reference> {
+	/// Array reference.
+	array: R,
+
+	/// Range of elements in the array.
+	range: Range<u64>,
+
+	/// Raw buffer handle.
+	handle: Handle,
+
+	/// Pointer to the first element (if the inner buffer is host accessible).
+	ptr: Option<*mut R::Item>
+}
+
+impl<R: Reference> WriteSlice<R> {
+	pub fn get(&self) -> Option<&[R::Item]> {
+		self.ptr.map(|ptr| unsafe { std::slice::from_raw_parts(ptr, (self.range.end - self.range.start) as usize) })
+	}
+
+	pub fn get_mut(&mut self) -> Option<&mut [R::Item]> {
+		use sub::Read as _;
+		let ptr = self.ptr?;
+		let offset = self.byte_offset();
+		self.array.read().inner.memory_slot().mark_dirty(offset..(offset + self.byte_len()));
+		Some(unsafe { std::slice::from_raw_parts_mut(ptr, (self.range.end - self.range.start) as usize) })
+	}
+}
+
+unsafe impl<R: Reference> resource::Reference for WriteSlice<R> {
+	type Handle = Handle;
+
+	fn handle(&self) -> Handle {
+		self.handle
+	}
+}
+
+unsafe impl<R: Reference> sub::Read for WriteSlice<R> {
+	type Usage = R::Usage;
+
+	fn byte_offset(&self) -> u64 {
+		self.range.start * std::mem::size_of::<R::Item>() as u64
+	}
+
+	fn byte_len(&self) -> u64 {
+		(self.range.end - self.range.start) * std::mem::size_of::<R::Item>() as u64
+	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		self.array.read().inner.memory_slot().host_writes().cloned()
+	}
+}
+
+unsafe impl<R: Reference> sub::Write for WriteSlice<R> {}
+
+unsafe impl<R: Reference> sub::TypedRead for WriteSlice<R> {
+	type Item = R::Item;
+
+	fn len(&self) -> u64 {
+		self.range.end - self.range.start
+	}
+}
+
+unsafe impl<R: Reference> sub::TypedWrite for WriteSlice<R> {}
+
+unsafe impl<R: Reference + Send> Send for WriteSlice<R> where R::Item: Send {}
+
+impl<R: Reference> Drop for WriteSlice<R> {
+	fn drop(&mut self) {
+		self.array.read().release_range(self.range.clone())
+	}
+}
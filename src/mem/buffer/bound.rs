@@ -1,6 +1,9 @@
 use std::{
 	sync::Arc,
-	ops::Deref
+	ops::{
+		Deref,
+		RangeBounds
+	}
 };
 use ash::{
 	vk,
@@ -9,24 +12,31 @@ use ash::{
 use crate::{
 	Device,
 	DeviceOwned,
+	device::MissingExtensionError,
 	mem::{
 		Slot,
 		HostVisible,
+		HostWrites,
 		Allocator,
-		buffer
+		buffer::{
+			self,
+			Usages,
+			usage,
+			sub::Slice
+		}
 	},
 	resource
 };
 use super::Typed;
 
 /// Bound buffer.
-pub struct Bound<S: Slot> {
-	inner: buffer::Unbound,
+pub struct Bound<S: Slot, U: usage::Set = Usages> {
+	inner: buffer::Unbound<U>,
 	slot: S
 }
 
-impl<S: Slot> Bound<S> {
-	pub(crate) fn new(inner: buffer::Unbound, slot: S) -> Self {
+impl<S: Slot, U: usage::Set> Bound<S, U> {
+	pub(crate) fn new(inner: buffer::Unbound<U>, slot: S) -> Self {
 		Bound {
 			inner,
 			slot
@@ -37,16 +47,97 @@ impl<S: Slot> Bound<S> {
 		&self.slot
 	}
 
+	#[inline]
+	pub(crate) fn unbound(&self) -> &buffer::Unbound<U> {
+		&self.inner
+	}
+
+	/// Device address of the buffer, to be used in shaders through physical storage buffer pointers.
+	///
+	/// The buffer must have been created with the `shader_device_address` usage.
+	/// Requires the `VK_KHR_buffer_device_address` extension.
+	pub fn device_address(&self) -> Result<u64, MissingExtensionError> {
+		debug_assert!(self.inner.usage().shader_device_address());
+		let ext = self.inner.device().ext_khr_buffer_device_address()?;
+		let infos = vk::BufferDeviceAddressInfo {
+			buffer: self.inner.handle(),
+			..Default::default()
+		};
+
+		Ok(unsafe {
+			ext.get_buffer_device_address_khr(self.inner.device().handle().handle(), &infos)
+		})
+	}
+
+	/// Opaque capture address of the buffer, used by tools to replay its address
+	/// with `buffer::Unbound::new_for_capture_replay`.
+	///
+	/// The buffer must have been created with `buffer::Unbound::new_for_capture_replay`.
+	pub fn opaque_capture_address(&self) -> Result<u64, MissingExtensionError> {
+		let ext = self.inner.device().ext_khr_buffer_device_address()?;
+		let infos = vk::BufferDeviceAddressInfo {
+			buffer: self.inner.handle(),
+			..Default::default()
+		};
+
+		Ok(unsafe {
+			ext.get_buffer_opaque_capture_address_khr(self.inner.device().handle().handle(), &infos)
+		})
+	}
+
 	/// Releases the buffer and returns its memory slot.
 	pub fn unbind(self) -> S {
 		self.slot
 	}
 
-	pub unsafe fn into_typed<T>(self) -> Typed<T, S> {
+	/// Returns a view of the given byte range of the buffer.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	pub fn slice<R: RangeBounds<u64>>(&self, range: R) -> Slice<&Self> {
+		Slice::new(self, range)
+	}
+
+	/// Returns a mutable view of the given byte range of the buffer.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	pub fn slice_mut<R: RangeBounds<u64>>(&mut self, range: R) -> Slice<&mut Self> {
+		Slice::new(self, range)
+	}
+
+	/// Turns the buffer into a view of the given byte range.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	pub fn into_slice<R: RangeBounds<u64>>(self, range: R) -> Slice<Self> {
+		Slice::new(self, range)
+	}
+
+	/// Changes the usage set type of the buffer, if its usages include all the given ones.
+	///
+	/// See `buffer::Unbound::into_static_usage`.
+	pub fn into_static_usage<V: usage::Set>(self, usage: V) -> Result<Bound<S, V>, Self> {
+		match self.inner.into_static_usage(usage) {
+			Ok(inner) => Ok(Bound {
+				inner,
+				slot: self.slot
+			}),
+			Err(inner) => Err(Bound {
+				inner,
+				slot: self.slot
+			})
+		}
+	}
+
+	pub unsafe fn into_typed<T>(self) -> Typed<T, S, U> {
 		Typed::from_raw_parts(self.inner, self.slot)
 	}
 
-	pub fn boxed(self) -> Bound<Box<dyn Send + Slot>> where S: Send {
+	pub fn boxed(self) -> Bound<Box<dyn Send + Slot>, U> where S: Send {
 		Bound {
 			inner: self.inner,
 			slot: Box::new(self.slot)
@@ -60,7 +151,7 @@ impl<S: Slot> Bound<S> {
 // 	}
 // }
 
-unsafe impl<S: Slot> resource::Reference for Bound<S> {
+unsafe impl<S: Slot, U: usage::Set> resource::Reference for Bound<S, U> {
 	type Handle = vk::Buffer;
 
 	fn handle(&self) -> vk::Buffer {
@@ -68,7 +159,9 @@ unsafe impl<S: Slot> resource::Reference for Bound<S> {
 	}
 }
 
-unsafe impl<S: Slot> buffer::sub::Read for Bound<S> {
+unsafe impl<S: Slot, U: usage::Set> buffer::sub::Read for Bound<S, U> {
+	type Usage = U;
+
 	fn byte_offset(&self) -> u64 {
 		0
 	}
@@ -76,11 +169,42 @@ unsafe impl<S: Slot> buffer::sub::Read for Bound<S> {
 	fn byte_len(&self) -> u64 {
 		self.inner.len()
 	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		self.slot.host_writes().cloned()
+	}
+}
+
+unsafe impl<S: Slot, U: usage::Set> buffer::sub::Write for Bound<S, U> {}
+
+/// Shared bound buffer, written by a command recorded while other references to it may exist.
+///
+/// Used to record transfers to a buffer kept alive by the recorded command buffer.
+pub(crate) struct Shared<S: Slot, U: usage::Set = Usages>(pub(crate) Arc<Bound<S, U>>);
+
+unsafe impl<S: Slot, U: usage::Set> resource::Reference for Shared<S, U> {
+	type Handle = vk::Buffer;
+
+	fn handle(&self) -> vk::Buffer {
+		self.0.inner.handle()
+	}
+}
+
+unsafe impl<S: Slot, U: usage::Set> buffer::sub::Read for Shared<S, U> {
+	type Usage = U;
+
+	fn byte_offset(&self) -> u64 {
+		0
+	}
+
+	fn byte_len(&self) -> u64 {
+		self.0.inner.len()
+	}
 }
 
-unsafe impl<S: Slot> buffer::sub::Write for Bound<S> {}
+unsafe impl<S: Slot, U: usage::Set> buffer::sub::Write for Shared<S, U> {}
 
-impl<S: Slot> DeviceOwned for Bound<S> {
+impl<S: Slot, U: usage::Set> DeviceOwned for Bound<S, U> {
 	fn device(&self) -> &Arc<Device> {
 		self.inner.device()
 	}
@@ -0,0 +1,406 @@
+use std::{
+	sync::Arc,
+	fmt
+};
+use ash::{
+	vk,
+	version::DeviceV1_0
+};
+use crate::{
+	Device,
+	DeviceOwned,
+	device::{
+		Queue,
+		queue::SubmitError
+	},
+	command::{
+		self,
+		buffer::{
+			Recorded,
+			RecordError
+		}
+	},
+	mem::{
+		Slot,
+		Allocator,
+		HostVisible,
+		MemoryUsage,
+		buffer::{
+			self,
+			Usages,
+			usage,
+			Unbound,
+			Bound
+		},
+		staging
+	},
+	sync::{
+		SharingQueues,
+		Task,
+		fence::{
+			self,
+			Fence
+		}
+	}
+};
+use super::vec::Error;
+
+/// Device-local vector upload error.
+#[derive(Debug)]
+pub enum UploadError {
+	Buffer(Error),
+	Record(RecordError),
+	Submit(SubmitError)
+}
+
+impl fmt::Display for UploadError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			UploadError::Buffer(e) => e.fmt(f),
+			UploadError::Record(e) => write!(f, "command buffer recording failed: {:?}", e),
+			UploadError::Submit(e) => write!(f, "submission failed: {}", e)
+		}
+	}
+}
+
+impl std::error::Error for UploadError {
+	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+		match self {
+			UploadError::Buffer(e) => Some(e),
+			UploadError::Record(_) => None,
+			UploadError::Submit(e) => Some(e)
+		}
+	}
+}
+
+impl From<Error> for UploadError {
+	fn from(e: Error) -> Self {
+		Self::Buffer(e)
+	}
+}
+
+impl From<RecordError> for UploadError {
+	fn from(e: RecordError) -> Self {
+		Self::Record(e)
+	}
+}
+
+impl From<SubmitError> for UploadError {
+	fn from(e: SubmitError) -> Self {
+		Self::Submit(e)
+	}
+}
+
+/// Growable vector living in device-local memory.
+///
+/// Unlike `buffer::Vec`, the content of the vector is not host visible.
+/// Pushed items are staged on the host and only transferred to the device buffer
+/// by `upload` (or `record_upload`).
+/// When the device buffer is too small to hold the whole vector,
+/// a bigger buffer is allocated and the old content is copied on the device.
+///
+/// The usages `U` of the device buffer must include the transfer source and destination usages,
+/// used to grow the buffer and to upload the staged items.
+pub struct DeviceVec<T, A: Allocator, S: Allocator, U: usage::Set> {
+	allocator: A,
+	staging: staging::Allocator<S>,
+	usage: U,
+	sharing_mode: SharingQueues,
+	buffer: Option<Arc<Bound<A::Slot, U>>>,
+	capacity: u64,
+
+	/// Number of items stored in the device buffer.
+	device_len: u64,
+
+	/// Items pushed after `device_len`, not yet uploaded.
+	staged: std::vec::Vec<T>
+}
+
+impl<T, A: Allocator, S: Allocator, U: usage::Set> DeviceVec<T, A, S, U> {
+	pub fn device(&self) -> &Arc<Device> {
+		self.allocator.device()
+	}
+
+	pub fn usage(&self) -> Usages {
+		self.usage.usages()
+	}
+
+	/// Number of items the device buffer can hold without growing.
+	pub fn capacity(&self) -> u64 {
+		self.capacity
+	}
+
+	/// Length of the vector, including the staged items.
+	pub fn len(&self) -> u64 {
+		self.device_len + self.staged.len() as u64
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Number of items not yet uploaded to the device buffer.
+	pub fn staged_len(&self) -> u64 {
+		self.staged.len() as u64
+	}
+
+	/// Current device buffer, if any.
+	///
+	/// Only the first `len() - staged_len()` items are initialized.
+	/// The buffer is replaced when the vector grows during an upload.
+	pub fn buffer(&self) -> Option<&Bound<A::Slot, U>> {
+		self.buffer.as_deref()
+	}
+}
+
+impl<T: Copy, A: Allocator, S: Allocator, U: usage::TransferSource + usage::TransferDestination> DeviceVec<T, A, S, U> {
+	pub fn new<Q: Into<SharingQueues>>(allocator: A, staging: staging::Allocator<S>, initial_capacity: u64, usage: U, sharing_queues: Q) -> Result<Self, Error> {
+		let mut this = Self {
+			allocator,
+			staging,
+			usage,
+			sharing_mode: sharing_queues.into(),
+			buffer: None,
+			capacity: 0,
+			device_len: 0,
+			staged: std::vec::Vec::new()
+		};
+
+		if initial_capacity > 0 {
+			this.buffer = Some(Arc::new(this.allocate_buffer(initial_capacity)?));
+			this.capacity = initial_capacity;
+		}
+
+		Ok(this)
+	}
+
+	fn allocate_buffer(&self, capacity: u64) -> Result<Bound<A::Slot, U>, Error> {
+		let layout = std::alloc::Layout::new::<T>();
+
+		let buffer = Unbound::new(
+			self.device(),
+			capacity * layout.size() as u64,
+			self.usage,
+			self.sharing_mode.clone()
+		)?;
+
+		let memory_requirements = buffer.memory_requirements().align_to(layout.align() as u64).with_usage(MemoryUsage::GpuOnly);
+		let slot = self.allocator.allocate(memory_requirements)?;
+
+		unsafe {
+			buffer.bind(slot).map_err(|(_, e)| e.into())
+		}
+	}
+
+	/// Allocates a host-visible buffer holding the staged items.
+	fn stage(&self) -> Result<Bound<HostVisible<S::Slot>, usage::Upload>, Error> {
+		let layout = std::alloc::Layout::new::<T>();
+
+		let buffer = Unbound::new(
+			self.device(),
+			self.staged.len() as u64 * layout.size() as u64,
+			usage::Upload,
+			self.sharing_mode.clone()
+		)?;
+
+		let memory_requirements = buffer.memory_requirements().align_to(layout.align() as u64);
+		let slot = self.staging.allocate(memory_requirements)?;
+
+		// The items are copied without viewing the uninitialized slot as a slice of `T`.
+		let ptr = slot.view_ptr::<T>(0, self.staged.len()).expect("invalid staging slot view");
+		unsafe {
+			std::ptr::copy_nonoverlapping(self.staged.as_ptr(), ptr, self.staged.len())
+		}
+		slot.mark_dirty(0..(self.staged.len() * layout.size()) as u64);
+
+		unsafe {
+			buffer.bind(slot).map_err(|(_, e)| e.into())
+		}
+	}
+
+	/// Resizes the vector, filling the new items with the given value.
+	///
+	/// The new items are staged until the next upload.
+	pub fn resize(&mut self, new_len: u64, value: T) {
+		if new_len < self.device_len {
+			self.device_len = new_len;
+			self.staged.clear()
+		} else {
+			self.staged.resize((new_len - self.device_len) as usize, value)
+		}
+	}
+
+	/// Pushes an item at the end of the vector.
+	///
+	/// The item is staged until the next upload.
+	pub fn push(&mut self, value: T) {
+		self.staged.push(value)
+	}
+
+	/// Records the transfer of the staged items to the device buffer.
+	///
+	/// If the device buffer is too small, it is replaced by a bigger one
+	/// and its content is copied to the new buffer with `Recorder::copy_buffer`.
+	/// The returned recorded command buffer holds the old buffer and the staging buffer,
+	/// which are released once it is executed and dropped.
+	///
+	/// The vector is updated as if the transfer was already done:
+	/// the device buffer must not be used before the command buffer is executed,
+	/// and the staged items are lost if it is never submitted.
+	/// Previous uses of the old device buffer must be complete
+	/// or be submitted to the same queue before the command buffer.
+	pub fn record_upload<B: command::Buffer + Send>(&mut self, command_buffer: B) -> Result<Recorded<B, Transfer<A::Slot, S::Slot, U>>, UploadError> where A::Slot: Send + Sync, S::Slot: Send + Sync {
+		let (recorded, capacity) = self.record_transfer(command_buffer)?;
+		self.commit(recorded.resources().buffer.clone(), capacity);
+		Ok(recorded)
+	}
+
+	/// Records the transfer of the staged items, without updating the vector.
+	///
+	/// Returns the recorded command buffer along with the capacity of the destination buffer.
+	fn record_transfer<B: command::Buffer + Send>(&self, command_buffer: B) -> Result<(Recorded<B, Transfer<A::Slot, S::Slot, U>>, u64), UploadError> where A::Slot: Send + Sync, S::Slot: Send + Sync {
+		let len = self.len();
+		let item_size = std::mem::size_of::<T>() as u64;
+
+		let (buffer, capacity, old) = if len > self.capacity {
+			let mut new_capacity = std::cmp::max(self.capacity, 1);
+			while new_capacity < len {
+				new_capacity *= 2;
+			}
+
+			let buffer = Arc::new(self.allocate_buffer(new_capacity)?);
+			(buffer, new_capacity, self.buffer.clone())
+		} else {
+			match &self.buffer {
+				Some(buffer) => (buffer.clone(), self.capacity, None),
+				None => {
+					// The vector is empty: it gets a minimal buffer so that it always has one after an upload.
+					let buffer = Arc::new(self.allocate_buffer(1)?);
+					(buffer, 1, None)
+				}
+			}
+		};
+
+		let staging = if self.staged.is_empty() {
+			None
+		} else {
+			Some(self.stage()?)
+		};
+
+		let transfer = Transfer {
+			buffer,
+			old,
+			staging,
+			copied_len: self.device_len * item_size,
+			staged_offset: self.device_len * item_size
+		};
+
+		let recorded = command_buffer.record(transfer, |recorder, transfer| {
+			let device = recorder.buffer.device().handle();
+			let cb = recorder.buffer.handle();
+
+			let memory_before = vk::MemoryBarrier {
+				src_access_mask: vk::AccessFlags::MEMORY_WRITE,
+				dst_access_mask: vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::TRANSFER_WRITE,
+				..Default::default()
+			};
+
+			unsafe {
+				device.cmd_pipeline_barrier(cb, vk::PipelineStageFlags::ALL_COMMANDS, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[memory_before], &[], &[]);
+			}
+
+			if let Some(old) = &transfer.old {
+				if transfer.copied_len > 0 {
+					let region = command::buffer::BufferCopy {
+						src_offset: 0,
+						dst_offset: 0,
+						size: transfer.copied_len
+					};
+
+					recorder.copy_buffer(old, buffer::Shared(transfer.buffer.clone()), &[region])
+				}
+			}
+
+			if let Some(staging) = &transfer.staging {
+				let region = command::buffer::BufferCopy {
+					src_offset: 0,
+					dst_offset: transfer.staged_offset,
+					size: staging.unbound().len()
+				};
+
+				recorder.copy_buffer(staging, buffer::Shared(transfer.buffer.clone()), &[region])
+			}
+
+			let memory_after = vk::MemoryBarrier {
+				src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+				dst_access_mask: vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
+				..Default::default()
+			};
+
+			unsafe {
+				recorder.buffer.device().handle().cmd_pipeline_barrier(recorder.buffer.handle(), vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[memory_after], &[], &[]);
+			}
+		})?;
+
+		Ok((recorded, capacity))
+	}
+
+	/// Updates the vector once its staged items are transferred to the given device buffer.
+	fn commit(&mut self, buffer: Arc<Bound<A::Slot, U>>, capacity: u64) {
+		self.device_len = self.len();
+		self.buffer = Some(buffer);
+		self.capacity = capacity;
+		self.staged.clear()
+	}
+
+	/// Transfers the staged items to the device buffer, growing it if necessary.
+	///
+	/// The transfer is recorded in the given command buffer (see `record_upload`)
+	/// and submitted to the given queue.
+	/// The returned future keeps the old device buffer and the staging buffer alive
+	/// until the fence is signaled.
+	///
+	/// The vector is only updated if the submission succeeds.
+	/// Otherwise the staged items are kept for the next upload.
+	pub fn upload<B: command::Buffer + Send, F: Fence>(&mut self, queue: &Queue, command_buffer: B, fence: F) -> Result<fence::Future<Recorded<B, Transfer<A::Slot, S::Slot, U>>, F>, UploadError> where A::Slot: Send + Sync, S::Slot: Send + Sync {
+		let (recorded, capacity) = self.record_transfer(command_buffer)?;
+		let buffer = recorded.resources().buffer.clone();
+		let ((), future) = queue.submit(recorded).then_signal_fence(fence)?;
+		self.commit(buffer, capacity);
+		Ok(future)
+	}
+}
+
+impl<T, A: Allocator, S: Allocator, U: usage::Set> DeviceOwned for DeviceVec<T, A, S, U> {
+	fn device(&self) -> &Arc<Device> {
+		self.allocator.device()
+	}
+}
+
+/// Resources of a `DeviceVec` upload.
+///
+/// Holds the buffers read by the transfer until the recorded command buffer is dropped.
+pub struct Transfer<S: Slot, H: Slot, U: usage::Set> {
+	/// Destination device buffer.
+	buffer: Arc<Bound<S, U>>,
+
+	/// Old device buffer, if the vector has grown.
+	old: Option<Arc<Bound<S, U>>>,
+
+	/// Staged items.
+	staging: Option<Bound<HostVisible<H>, usage::Upload>>,
+
+	/// Number of bytes copied from the old buffer.
+	copied_len: u64,
+
+	/// Offset of the staged items in the destination buffer.
+	staged_offset: u64
+}
+
+impl<S: Slot, H: Slot, U: usage::Set> Transfer<S, H, U> {
+	/// Checks if the vector has grown, replacing its device buffer.
+	#[inline]
+	pub fn has_grown(&self) -> bool {
+		self.old.is_some()
+	}
+}
@@ -0,0 +1,296 @@
+use std::{
+	marker::PhantomData,
+	sync::Arc,
+	fmt
+};
+use ash::{
+	vk,
+	version::DeviceV1_0
+};
+use crate::{
+	OomError,
+	DeviceOwned,
+	device::{
+		Queue,
+		queue::SubmitError
+	},
+	command::{
+		self,
+		buffer::{
+			Recorded,
+			RecordError
+		}
+	},
+	mem::{
+		Slot,
+		Allocator,
+		HostVisible,
+		MemoryUsage,
+		Pod,
+		buffer::{
+			usage,
+			Unbound,
+			Bound,
+			Shared,
+			sub
+		},
+		staging
+	},
+	sync::{
+		Task,
+		future,
+		fence::{
+			self,
+			Fence
+		}
+	}
+};
+use super::vec;
+
+/// Readback error.
+#[derive(Debug)]
+pub enum Error {
+	Buffer(vec::Error),
+	Record(RecordError),
+	Submit(SubmitError)
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Buffer(e) => e.fmt(f),
+			Error::Record(e) => write!(f, "command buffer recording failed: {:?}", e),
+			Error::Submit(e) => write!(f, "submission failed: {}", e)
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+		match self {
+			Error::Buffer(e) => Some(e),
+			Error::Record(_) => None,
+			Error::Submit(e) => Some(e)
+		}
+	}
+}
+
+impl From<vec::Error> for Error {
+	fn from(e: vec::Error) -> Self {
+		Self::Buffer(e)
+	}
+}
+
+impl From<RecordError> for Error {
+	fn from(e: RecordError) -> Self {
+		Self::Record(e)
+	}
+}
+
+impl From<SubmitError> for Error {
+	fn from(e: SubmitError) -> Self {
+		Self::Submit(e)
+	}
+}
+
+/// Readback wait error.
+#[derive(Debug)]
+pub enum WaitError {
+	OutOfMemory(OomError),
+	DeviceLost
+}
+
+impl fmt::Display for WaitError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WaitError::OutOfMemory(e) => e.fmt(f),
+			WaitError::DeviceLost => write!(f, "device lost")
+		}
+	}
+}
+
+impl std::error::Error for WaitError {
+	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+		match self {
+			WaitError::OutOfMemory(e) => Some(e),
+			WaitError::DeviceLost => None
+		}
+	}
+}
+
+impl From<fence::WaitError> for WaitError {
+	fn from(e: fence::WaitError) -> Self {
+		match e {
+			fence::WaitError::OutOfMemory(e) => WaitError::OutOfMemory(e),
+			fence::WaitError::DeviceLost => WaitError::DeviceLost,
+			fence::WaitError::Timeout => unreachable!() // the readback is given back on timeout.
+		}
+	}
+}
+
+impl From<OomError> for WaitError {
+	fn from(e: OomError) -> Self {
+		WaitError::OutOfMemory(e)
+	}
+}
+
+/// Resources of a readback.
+///
+/// Holds the source buffer and the host-visible readback buffer
+/// until the recorded command buffer is dropped.
+pub struct Transfer<B, S: Slot> {
+	/// Source buffer.
+	src: B,
+
+	/// Readback buffer.
+	dst: Arc<Bound<HostVisible<S>, usage::Readback>>
+}
+
+impl<B, S: Slot> Transfer<B, S> {
+	/// Source buffer.
+	#[inline]
+	pub fn source(&self) -> &B {
+		&self.src
+	}
+}
+
+/// Copies the content of the given buffer into host-visible memory.
+///
+/// A readback buffer is allocated with the given staging allocator,
+/// and the copy is recorded into the given command buffer, submitted to the given queue.
+/// Device writes to the source buffer submitted before are visible to the copy.
+/// The source buffer must have the `TransferSource` usage.
+///
+/// The returned future holds the source buffer until the copy is done.
+/// Its `wait` function returns the content of the buffer.
+pub fn readback<T, B, C, A, F>(src: B, allocator: &staging::Allocator<A>, queue: &Queue, command_buffer: C, fence: F) -> Result<Readback<T, B, C, A::Slot, F>, Error>
+where
+	T: Pod,
+	B: sub::TypedRead<Item = T> + Send + Sync,
+	B::Usage: usage::TransferSource,
+	C: command::Buffer + Send,
+	A: Allocator,
+	A::Slot: Send + Sync,
+	F: Fence
+{
+	let layout = std::alloc::Layout::new::<T>();
+	let len = src.len();
+
+	let buffer = Unbound::new(
+		allocator.device(),
+		std::cmp::max(len * layout.size() as u64, 1),
+		usage::Readback,
+		Some(queue)
+	).map_err(vec::Error::from)?;
+
+	let memory_requirements = buffer.memory_requirements().align_to(layout.align() as u64).with_usage(MemoryUsage::Readback);
+	let slot = allocator.allocate(memory_requirements).map_err(vec::Error::from)?;
+	let dst = unsafe {
+		buffer.bind(slot).map_err(|(_, e)| vec::Error::from(e))?
+	};
+
+	let transfer = Transfer {
+		src,
+		dst: Arc::new(dst)
+	};
+
+	let recorded = command_buffer.record(transfer, |recorder, transfer| {
+		let memory_before = vk::MemoryBarrier {
+			src_access_mask: vk::AccessFlags::MEMORY_WRITE,
+			dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+			..Default::default()
+		};
+
+		unsafe {
+			recorder.buffer.device().handle().cmd_pipeline_barrier(recorder.buffer.handle(), vk::PipelineStageFlags::ALL_COMMANDS, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[memory_before], &[], &[]);
+		}
+
+		if len > 0 {
+			let region = command::buffer::BufferCopy {
+				src_offset: 0,
+				dst_offset: 0,
+				size: len * layout.size() as u64
+			};
+
+			recorder.copy_buffer(&transfer.src, Shared(transfer.dst.clone()), &[region])
+		}
+
+		// Makes the copy visible to the host.
+		let memory_after = vk::MemoryBarrier {
+			src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+			dst_access_mask: vk::AccessFlags::HOST_READ,
+			..Default::default()
+		};
+
+		unsafe {
+			recorder.buffer.device().handle().cmd_pipeline_barrier(recorder.buffer.handle(), vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::HOST, vk::DependencyFlags::empty(), &[memory_after], &[], &[]);
+		}
+	})?;
+
+	let ((), future) = queue.submit(recorded).then_signal_fence(fence)?;
+
+	Ok(Readback {
+		future,
+		len,
+		t: PhantomData
+	})
+}
+
+/// Pending readback, created by `readback`.
+#[must_use]
+pub struct Readback<T, B, C: command::Buffer, S: Slot, F> {
+	future: fence::Future<Recorded<C, Transfer<B, S>>, F>,
+	len: u64,
+	t: PhantomData<T>
+}
+
+impl<T: Pod, B, C: command::Buffer, S: Slot, F: Fence> Readback<T, B, C, S, F> {
+	/// Number of elements read back.
+	#[inline]
+	pub fn len(&self) -> u64 {
+		self.len
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Checks if the copy is done.
+	pub fn is_signaled(&self) -> Result<bool, fence::DeviceLost> {
+		use future::SignalFence;
+		self.future.is_signaled()
+	}
+
+	/// Block until the copy is done, and returns the content of the buffer.
+	///
+	/// The readback memory is invalidated first if it is not host coherent.
+	/// The readback is given back if the copy is not done before the timeout (in nanoseconds).
+	pub fn wait(self, timeout: Option<u64>) -> Result<Result<Vec<T>, Self>, WaitError> {
+		let len = self.len as usize;
+		let recorded = match self.future.wait_payload(timeout)? {
+			Ok(recorded) => recorded,
+			Err(future) => return Ok(Err(Readback {
+				future,
+				len: self.len,
+				t: PhantomData
+			}))
+		};
+
+		let slot = recorded.resources().dst.memory_slot();
+
+		slot.invalidate(0..(len * std::mem::size_of::<T>()) as u64)?;
+
+		let data = unsafe {
+			std::slice::from_raw_parts(slot.ptr() as *const T, len)
+		};
+
+		Ok(Ok(data.to_vec()))
+	}
+}
+
+unsafe impl<T, B, C: command::Buffer, S: Slot, F: Fence> future::Future for Readback<T, B, C, S, F> {
+	fn signal_fence(&self) -> Option<&vk::Fence> {
+		future::Future::signal_fence(&self.future)
+	}
+}
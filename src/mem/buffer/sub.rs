@@ -1,7 +1,17 @@
 use std::ops::{
 	Deref,
-	DerefMut
+	DerefMut,
+	Range,
+	RangeBounds,
+	Bound
 };
+use std::sync::Arc;
+use ash::vk;
+use crate::{
+	resource,
+	mem::HostWrites
+};
+use super::usage;
 pub use super::Reference;
 
 // pub mod index;
@@ -10,15 +20,29 @@ pub use super::Reference;
 
 /// Sub buffer reader.
 pub unsafe trait Read: Reference {
+	/// Usages of the buffer, checked by the commands using it.
+	type Usage: usage::Set;
+
 	/// Byte offset in the buffer.
 	fn byte_offset(&self) -> u64;
 
 	/// Byte length of the subbuffer range.
 	fn byte_len(&self) -> u64;
+
+	/// Host writes tracker of the buffer memory.
+	///
+	/// This is called when a command reading the buffer is recorded,
+	/// and the range written by the host is flushed each time the command buffer is submitted.
+	#[inline]
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		None
+	}
 }
 
 /// Anything that deref into a buffer can be considered as a buffer.
 unsafe impl<B: Deref> Read for B where B::Target: Read {
+	type Usage = <B::Target as Read>::Usage;
+
 	fn byte_offset(&self) -> u64 {
 		Deref::deref(self).byte_offset()
 	}
@@ -26,6 +50,10 @@ unsafe impl<B: Deref> Read for B where B::Target: Read {
 	fn byte_len(&self) -> u64 {
 		Deref::deref(self).byte_len()
 	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		Deref::deref(self).host_writes()
+	}
 }
 
 pub unsafe trait Write: Read {
@@ -53,4 +81,126 @@ pub unsafe trait TypedWrite: TypedRead {
 	// ...
 }
 
-unsafe impl<B: DerefMut> TypedWrite for B where B::Target: TypedWrite { }
\ No newline at end of file
+unsafe impl<B: DerefMut> TypedWrite for B where B::Target: TypedWrite { }
+
+/// Resolves a range of items of a buffer of length `len`.
+///
+/// ## Panics
+///
+/// This function panics if the range is out of bounds.
+pub(crate) fn resolve_range<R: RangeBounds<u64>>(range: R, len: u64) -> Range<u64> {
+	let start = match range.start_bound() {
+		Bound::Included(i) => *i,
+		Bound::Excluded(i) => i + 1,
+		Bound::Unbounded => 0
+	};
+
+	let end = match range.end_bound() {
+		Bound::Included(i) => i + 1,
+		Bound::Excluded(i) => *i,
+		Bound::Unbounded => len
+	};
+
+	assert!(start <= end, "slice index starts at {} but ends at {}", start, end);
+	assert!(end <= len, "range end index {} out of range for buffer of length {}", end, len);
+
+	start..end
+}
+
+/// Sub-buffer view.
+///
+/// Covers a byte range of the underlying buffer view,
+/// which may be borrowed (`Slice<&B>`), mutably borrowed (`Slice<&mut B>`) or owned.
+/// Slices are created with the `slice` functions of `buffer::Typed`, `buffer::Bound`
+/// and `buffer::Array`, or with `Slice::new`.
+pub struct Slice<B> {
+	buffer: B,
+
+	/// Byte offset in the underlying buffer view.
+	offset: u64,
+
+	/// Byte length of the slice.
+	len: u64
+}
+
+impl<B: Read> Slice<B> {
+	/// Creates a slice covering the given byte range of the buffer.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	pub fn new<R: RangeBounds<u64>>(buffer: B, range: R) -> Self {
+		let range = resolve_range(range, buffer.byte_len());
+		Self {
+			buffer,
+			offset: range.start,
+			len: range.end - range.start
+		}
+	}
+
+	/// Creates a slice covering the given range of items of the buffer.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	pub fn new_typed<R: RangeBounds<u64>>(buffer: B, range: R) -> Self where B: TypedRead {
+		let item_size = std::mem::size_of::<B::Item>() as u64;
+		let range = resolve_range(range, buffer.len());
+		Self {
+			buffer,
+			offset: range.start * item_size,
+			len: (range.end - range.start) * item_size
+		}
+	}
+
+	/// Returns the underlying buffer view.
+	#[inline]
+	pub fn buffer(&self) -> &B {
+		&self.buffer
+	}
+
+	/// Returns the underlying buffer view.
+	#[inline]
+	pub fn into_inner(self) -> B {
+		self.buffer
+	}
+}
+
+unsafe impl<B: Read> resource::Reference for Slice<B> {
+	type Handle = vk::Buffer;
+
+	fn handle(&self) -> vk::Buffer {
+		self.buffer.handle()
+	}
+}
+
+unsafe impl<B: Read> Read for Slice<B> {
+	type Usage = B::Usage;
+
+	fn byte_offset(&self) -> u64 {
+		self.buffer.byte_offset() + self.offset
+	}
+
+	fn byte_len(&self) -> u64 {
+		self.len
+	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		self.buffer.host_writes()
+	}
+}
+
+unsafe impl<B: Write> Write for Slice<B> {}
+
+unsafe impl<B: TypedRead> TypedRead for Slice<B> {
+	type Item = B::Item;
+
+	fn len(&self) -> u64 {
+		match std::mem::size_of::<B::Item>() {
+			0 => 0,
+			item_size => self.len / item_size as u64
+		}
+	}
+}
+
+unsafe impl<B: TypedWrite> TypedWrite for Slice<B> {}
@@ -1,6 +1,9 @@
 use std::{
 	sync::Arc,
-	ops::Deref,
+	ops::{
+		Deref,
+		RangeBounds
+	},
 	marker::PhantomData
 };
 use ash::{
@@ -11,24 +14,35 @@ use crate::{
 	resource,
 	Device,
 	DeviceOwned,
+	device::Queue,
+	command,
 	mem::{
 		Slot,
 		HostVisible,
+		HostWrites,
 		Allocator,
-		buffer
-	}
+		Pod,
+		buffer::{
+			self,
+			Usages,
+			usage,
+			sub::Slice
+		},
+		staging
+	},
+	sync::Fence
 };
 
 /// Typed buffer.
-pub struct Typed<T, S> {
-	inner: buffer::Unbound,
+pub struct Typed<T, S, U: usage::Set = Usages> {
+	inner: buffer::Unbound<U>,
 	slot: S,
 	t: PhantomData<T>,
 	len: u64
 }
 
-impl<T, S> Typed<T, S> {
-	pub(crate) unsafe fn from_raw_parts(inner: buffer::Unbound, slot: S) -> Self {
+impl<T, S, U: usage::Set> Typed<T, S, U> {
+	pub(crate) unsafe fn from_raw_parts(inner: buffer::Unbound<U>, slot: S) -> Self {
 		let len = inner.len() / std::mem::size_of::<T>() as u64;
 		Self {
 			inner,
@@ -50,13 +64,57 @@ impl<T, S> Typed<T, S> {
 	}
 }
 
+impl<T, S: Slot, U: usage::Set> Typed<T, S, U> {
+	/// Returns a view of the given range of elements.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	pub fn slice<R: RangeBounds<u64>>(&self, range: R) -> Slice<&Self> {
+		Slice::new_typed(self, range)
+	}
+
+	/// Returns a mutable view of the given range of elements.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	pub fn slice_mut<R: RangeBounds<u64>>(&mut self, range: R) -> Slice<&mut Self> {
+		Slice::new_typed(self, range)
+	}
+
+	/// Turns the buffer into a view of the given range of elements.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the range is out of bounds.
+	pub fn into_slice<R: RangeBounds<u64>>(self, range: R) -> Slice<Self> {
+		Slice::new_typed(self, range)
+	}
+}
+
+impl<T: Pod + Send + Sync, S: Slot + Send + Sync, U: usage::TransferSource> Typed<T, S, U> {
+	/// Copies the content of the buffer into host-visible memory.
+	///
+	/// See `buffer::readback` for more details.
+	pub fn readback<'a, C, A, F>(&'a self, allocator: &staging::Allocator<A>, queue: &Queue, command_buffer: C, fence: F) -> Result<buffer::Readback<T, &'a Self, C, A::Slot, F>, buffer::readback::Error>
+	where
+		C: command::Buffer + Send,
+		A: Allocator,
+		A::Slot: Send + Sync,
+		F: Fence
+	{
+		buffer::readback(self, allocator, queue, command_buffer, fence)
+	}
+}
+
 // unsafe impl<T, S> resource::AbstractReference for Typed<T, S> {
 // 	fn uid(&self) -> u64 {
 // 		self.inner.handle().as_raw()
 // 	}
 // }
 
-unsafe impl<T, S> resource::Reference for Typed<T, S> {
+unsafe impl<T, S, U: usage::Set> resource::Reference for Typed<T, S, U> {
 	type Handle = vk::Buffer;
 
 	fn handle(&self) -> vk::Buffer {
@@ -64,7 +122,9 @@ unsafe impl<T, S> resource::Reference for Typed<T, S> {
 	}
 }
 
-unsafe impl<T, S> buffer::sub::Read for Typed<T, S> {
+unsafe impl<T, S: Slot, U: usage::Set> buffer::sub::Read for Typed<T, S, U> {
+	type Usage = U;
+
 	fn byte_offset(&self) -> u64 {
 		0
 	}
@@ -72,9 +132,15 @@ unsafe impl<T, S> buffer::sub::Read for Typed<T, S> {
 	fn byte_len(&self) -> u64 {
 		self.inner.len()
 	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		self.slot.host_writes().cloned()
+	}
 }
 
-unsafe impl<T, S> buffer::sub::TypedRead for Typed<T, S> {
+unsafe impl<T, S: Slot, U: usage::Set> buffer::sub::Write for Typed<T, S, U> {}
+
+unsafe impl<T, S: Slot, U: usage::Set> buffer::sub::TypedRead for Typed<T, S, U> {
 	type Item = T;
 
 	fn len(&self) -> u64 {
@@ -82,7 +148,9 @@ unsafe impl<T, S> buffer::sub::TypedRead for Typed<T, S> {
 	}
 }
 
-impl<T, S> DeviceOwned for Typed<T, S> {
+unsafe impl<T, S: Slot, U: usage::Set> buffer::sub::TypedWrite for Typed<T, S, U> {}
+
+impl<T, S, U: usage::Set> DeviceOwned for Typed<T, S, U> {
 	fn device(&self) -> &Arc<Device> {
 		self.inner.device()
 	}
@@ -9,15 +9,19 @@ use ash::{
 use crate::{
 	Device,
 	DeviceOwned,
+	device::external,
 	sync,
 	OomError,
 	mem::{
 		MemoryRequirements,
-		Slot
+		memory_requirements::Resource,
+		Slot,
+		sparse
 	}
 };
 use super::{
 	Usages,
+	usage,
 	Bound
 };
 
@@ -92,25 +96,95 @@ impl From<vk::Result> for BindError {
 	}
 }
 
-pub struct Unbound {
+/// Raw buffer, not yet bound to memory.
+///
+/// The usages `U` of the buffer may be statically known,
+/// allowing commands to check them at compile time (see `usage::Set`).
+pub struct Unbound<U: usage::Set = Usages> {
 	handle: vk::Buffer,
 	device: Arc<Device>,
 	size: u64,
-	usage: Usages
+	usage: U,
+	sharing_queues: sync::SharingQueues,
+	sparse: Option<sparse::Flags>,
+	external: external::HandleTypes,
+	opaque_capture_address: Option<u64>
 }
 
-impl Unbound {
+impl<U: usage::Set> Unbound<U> {
 	/// Create a raw, uninitialized buffer of the given size.
-	pub fn new<U: Into<Usages>, S: Into<sync::SharingQueues>>(device: &Arc<Device>, size: u64, usage: U, sharing_queues: S) -> Result<Self, CreationError> {
-		let usage = usage.into();
-		assert!(!usage.is_empty());
+	pub fn new<S: Into<sync::SharingQueues>>(device: &Arc<Device>, size: u64, usage: U, sharing_queues: S) -> Result<Self, CreationError> {
+		Self::create(device, size, usage, sharing_queues.into(), None, external::HandleTypes::none(), None)
+	}
+
+	/// Create a raw buffer whose memory can be exported to or imported from the given handle types.
+	///
+	/// The handle types should be negotiated with `PhysicalDevice::external_buffer_properties`.
+	/// This requires the `VK_KHR_external_memory` extension to be enabled.
+	pub fn new_external<S: Into<sync::SharingQueues>>(device: &Arc<Device>, size: u64, usage: U, sharing_queues: S, handle_types: external::HandleTypes) -> Result<Self, CreationError> {
+		Self::create(device, size, usage, sharing_queues.into(), None, handle_types, None)
+	}
+
+	/// Create a raw sparse buffer of the given size.
+	///
+	/// A sparse buffer cannot be bound with `bind`.
+	/// Its memory is bound page by page with `Queue::bind_sparse` once wrapped in a `sparse::Buffer`.
+	pub fn new_sparse<S: Into<sync::SharingQueues>>(device: &Arc<Device>, size: u64, usage: U, sharing_queues: S, flags: sparse::Flags) -> Result<Self, CreationError> {
+		Self::create(device, size, usage, sharing_queues.into(), Some(flags), external::HandleTypes::none(), None)
+	}
+
+	/// Create a raw buffer whose device address can be captured and replayed by tools.
+	///
+	/// If `opaque_capture_address` is 0, the buffer is created for capture,
+	/// and its address can be retrieved with `Bound::opaque_capture_address` once bound.
+	/// Otherwise, the buffer is created at the given previously captured address.
+	/// The buffer must be bound to memory allocated with `Device::allocate_memory_for_capture_replay`.
+	///
+	/// ## Panics
+	///
+	/// This function panics if buffer device address capture and replay is not enabled
+	/// (see `Device::buffer_device_address_features`).
+	pub fn new_for_capture_replay<S: Into<sync::SharingQueues>>(device: &Arc<Device>, size: u64, usage: U, sharing_queues: S, opaque_capture_address: u64) -> Result<Self, CreationError> {
+		assert!(device.buffer_device_address_features().map(|f| f.capture_replay).unwrap_or(false), "buffer device address capture and replay is not enabled");
+		Self::create(device, size, usage, sharing_queues.into(), None, external::HandleTypes::none(), Some(opaque_capture_address))
+	}
+
+	fn create(device: &Arc<Device>, size: u64, usage: U, sharing_queues: sync::SharingQueues, sparse: Option<sparse::Flags>, external: external::HandleTypes, opaque_capture_address: Option<u64>) -> Result<Self, CreationError> {
+		assert!(!usage.usages().is_empty());
 
-		let sharing_queues = sharing_queues.into();
 		let (sh_mode, sh_count, sh_indices) = sharing_queues.as_vulkan();
 
+		let external_infos = vk::ExternalMemoryBufferCreateInfo {
+			handle_types: external.into_vulkan(),
+			..Default::default()
+		};
+
+		let p_next = if external.is_empty() {
+			std::ptr::null()
+		} else {
+			&external_infos as *const _ as *const std::ffi::c_void
+		};
+
+		let capture_infos = vk::BufferOpaqueCaptureAddressCreateInfo {
+			p_next,
+			opaque_capture_address: opaque_capture_address.unwrap_or(0),
+			..Default::default()
+		};
+
+		let mut flags = sparse.map(|f| f.buffer_create_flags()).unwrap_or_default();
+		let p_next = match opaque_capture_address {
+			Some(_) => {
+				flags |= vk::BufferCreateFlags::DEVICE_ADDRESS_CAPTURE_REPLAY;
+				&capture_infos as *const _ as *const std::ffi::c_void
+			},
+			None => p_next
+		};
+
 		let infos = vk::BufferCreateInfo {
+			p_next,
+			flags,
 			size,
-			usage: usage.into_vulkan(),
+			usage: usage.usages().into_vulkan(),
 			sharing_mode: sh_mode,
 			queue_family_index_count: sh_count,
 			p_queue_family_indices: sh_indices,
@@ -125,10 +199,45 @@ impl Unbound {
 			handle,
 			device: device.clone(),
 			size,
-			usage
+			usage,
+			sharing_queues,
+			sparse,
+			external,
+			opaque_capture_address
 		})
 	}
 
+	/// Changes the usage set type of the buffer, if its usages include all the given ones.
+	///
+	/// This turns a buffer created with usages only known at run time
+	/// into a buffer with static usage markers (such as `usage::TransferSource`),
+	/// required by some commands.
+	/// The buffer is given back if it lacks some of the usages.
+	pub fn into_static_usage<V: usage::Set>(self, usage: V) -> Result<Unbound<V>, Self> {
+		if !self.usage().contains(usage.usages()) {
+			return Err(self)
+		}
+
+		let this = std::mem::ManuallyDrop::new(self);
+		unsafe {
+			Ok(Unbound {
+				handle: this.handle,
+				device: std::ptr::read(&this.device),
+				size: this.size,
+				usage,
+				sharing_queues: std::ptr::read(&this.sharing_queues),
+				sparse: this.sparse,
+				external: this.external,
+				opaque_capture_address: this.opaque_capture_address
+			})
+		}
+	}
+
+	/// Creates a new buffer with the same parameters.
+	pub(crate) fn duplicate(&self) -> Result<Self, CreationError> {
+		Self::create(&self.device, self.size, self.usage, self.sharing_queues.clone(), self.sparse, self.external, self.opaque_capture_address)
+	}
+
 	pub fn handle(&self) -> vk::Buffer {
 		self.handle
 	}
@@ -138,16 +247,32 @@ impl Unbound {
 		self.size
 	}
 
+	/// Sparse creation flags, if this is a sparse buffer.
+	#[inline]
+	pub fn sparse(&self) -> Option<sparse::Flags> {
+		self.sparse
+	}
+
+	/// Handle types the buffer memory can be exported to or imported from.
+	#[inline]
+	pub fn external_handle_types(&self) -> external::HandleTypes {
+		self.external
+	}
+
+	/// Usages of the buffer.
+	#[inline]
+	pub fn usage(&self) -> Usages {
+		self.usage.usages()
+	}
+
 	#[inline]
 	pub fn memory_requirements(&self) -> MemoryRequirements {
-		unsafe {
-			let mr = self.device.handle.get_buffer_memory_requirements(self.handle);
-			MemoryRequirements::new(mr, true)
-		}
+		MemoryRequirements::query(&self.device, Resource::Buffer(self.handle), true)
 	}
 
 	#[inline]
-	pub unsafe fn bind<S: Slot>(self, slot: S) -> Result<Bound<S>, (Self, BindError)> {
+	pub unsafe fn bind<S: Slot>(self, slot: S) -> Result<Bound<S, U>, (Self, BindError)> {
+		debug_assert!(self.sparse.is_none(), "sparse buffers cannot be bound to a single slot");
 		let memory = slot.memory();
 		
 		// We check for correctness in debug mode.
@@ -161,15 +286,16 @@ impl Unbound {
 		// Check for alignment correctness.
 		{
 			let limits = self.device.physical_device().limits();
-			if self.usage.uniform_texel_buffer() || self.usage.storage_texel_buffer() {
+			let usage = self.usage();
+			if usage.uniform_texel_buffer() || usage.storage_texel_buffer() {
 				debug_assert!(slot.offset() % limits.min_texel_buffer_offset_alignment() == 0);
 			}
 
-			if self.usage.storage_buffer() {
+			if usage.storage_buffer() {
 				debug_assert!(slot.offset() % limits.min_storage_buffer_offset_alignment() == 0);
 			}
 
-			if self.usage.uniform_buffer() {
+			if usage.uniform_buffer() {
 				debug_assert!(slot.offset() % limits.min_uniform_buffer_offset_alignment() == 0);
 			}
 		}
@@ -183,13 +309,13 @@ impl Unbound {
 	}
 }
 
-impl DeviceOwned for Unbound {
+impl<U: usage::Set> DeviceOwned for Unbound<U> {
 	fn device(&self) -> &Arc<Device> {
 		&self.device
 	}
 }
 
-impl Drop for Unbound {
+impl<U: usage::Set> Drop for Unbound<U> {
 	fn drop(&mut self) {
 		unsafe {
 			self.device.handle.destroy_buffer(self.handle, None);
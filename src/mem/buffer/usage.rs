@@ -11,7 +11,8 @@ pub enum Usage {
 	StorageBuffer = vk::BufferUsageFlags::STORAGE_BUFFER.as_raw(),
 	IndexBuffer = vk::BufferUsageFlags::INDEX_BUFFER.as_raw(),
 	VertexBuffer = vk::BufferUsageFlags::VERTEX_BUFFER.as_raw(),
-	IndirectBuffer = vk::BufferUsageFlags::INDIRECT_BUFFER.as_raw()
+	IndirectBuffer = vk::BufferUsageFlags::INDIRECT_BUFFER.as_raw(),
+	ShaderDeviceAddress = vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS.as_raw()
 }
 
 impl Usage {
@@ -40,6 +41,11 @@ impl std::ops::BitOr<Usages> for Usage {
 pub struct Usages(vk::BufferUsageFlags);
 
 impl Usages {
+	#[inline]
+	pub fn empty() -> Self {
+		Usages(vk::BufferUsageFlags::empty())
+	}
+
 	#[inline]
 	pub fn is_empty(&self) -> bool {
 		self.0.is_empty()
@@ -50,6 +56,12 @@ impl Usages {
 		self.0
 	}
 
+	/// Checks if all the given usages are included.
+	#[inline]
+	pub fn contains(&self, other: Usages) -> bool {
+		self.0.contains(other.0)
+	}
+
 	pub fn transfer_source(&self) -> bool {
 		self.0.contains(vk::BufferUsageFlags::TRANSFER_SRC)
 	}
@@ -85,6 +97,11 @@ impl Usages {
 	pub fn indirect_buffer(&self) -> bool {
 		self.0.contains(vk::BufferUsageFlags::INDIRECT_BUFFER)
 	}
+
+	/// The buffer device address can be queried with `Bound::device_address`.
+	pub fn shader_device_address(&self) -> bool {
+		self.0.contains(vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS)
+	}
 }
 
 impl From<Usage> for Usages {
@@ -119,4 +136,123 @@ impl std::ops::BitOrAssign<Usage> for Usages {
 	fn bitor_assign(&mut self, rhs: Usage) {
 		self.0 |= rhs.into_vulkan()
 	}
-}
\ No newline at end of file
+}
+
+/// Set of buffer usages.
+///
+/// The usages are either known at run time only (`Usage` and `Usages`),
+/// or statically encoded in the type (see [`buffer_usages_type!`]).
+/// Static usage sets additionally implement the marker trait of each of their usages
+/// (such as `usage::TransferSource`),
+/// which are required by commands using the buffer.
+pub trait Set: Copy + Send + Sync + 'static {
+	/// Usages of the set.
+	fn usages(&self) -> Usages;
+}
+
+impl Set for Usage {
+	fn usages(&self) -> Usages {
+		Usages::from(*self)
+	}
+}
+
+impl Set for Usages {
+	fn usages(&self) -> Usages {
+		*self
+	}
+}
+
+macro_rules! static_usages {
+	($($(#[$meta:meta])* $id:ident),*) => {
+		$(
+			$(#[$meta])*
+			///
+			/// ## Safety
+			///
+			/// `Set::usages` must always include this usage.
+			pub unsafe trait $id: Set {}
+		)*
+	};
+}
+
+static_usages! {
+	/// Statically known transfer source usage, required to copy from the buffer.
+	TransferSource,
+
+	/// Statically known transfer destination usage, required to copy to the buffer.
+	TransferDestination,
+
+	/// Statically known uniform texel buffer usage.
+	UniformTexelBuffer,
+
+	/// Statically known storage texel buffer usage.
+	StorageTexelBuffer,
+
+	/// Statically known uniform buffer usage.
+	UniformBuffer,
+
+	/// Statically known storage buffer usage.
+	StorageBuffer,
+
+	/// Statically known index buffer usage.
+	IndexBuffer,
+
+	/// Statically known vertex buffer usage.
+	VertexBuffer,
+
+	/// Statically known indirect buffer usage.
+	IndirectBuffer,
+
+	/// Statically known shader device address usage.
+	ShaderDeviceAddress
+}
+
+crate::buffer_usages_type! {
+	/// Usages of staging buffers, copied to device-local buffers.
+	pub struct Upload : TransferSource
+}
+
+crate::buffer_usages_type! {
+	/// Usages of readback buffers, to which device-local buffers are copied.
+	pub struct Readback : TransferDestination
+}
+
+/// Create a static buffer usage set type.
+///
+/// The produced type is a unit structure implementing `usage::Set`,
+/// and the static usage marker trait of each listed usage,
+/// so that the buffers created with it can be used by the commands requiring those usages.
+///
+/// ## Example
+///
+/// ```
+/// magma::buffer_usages_type! {
+///   /// Vertex buffer filled by transfers.
+///   pub struct VertexDestination : TransferDestination, VertexBuffer
+/// }
+/// ```
+#[macro_export]
+macro_rules! buffer_usages_type {
+	{
+		$(#[$meta:meta])*
+		$vis:vis struct $id:ident : $($usage:ident),+ $(,)?
+	} => {
+		$(#[$meta])*
+		#[derive(Clone, Copy, Default)]
+		$vis struct $id;
+
+		impl $crate::mem::buffer::usage::Set for $id {
+			fn usages(&self) -> $crate::mem::buffer::Usages {
+				let mut usages = $crate::mem::buffer::Usages::empty();
+				$(
+					usages |= $crate::mem::buffer::Usage::$usage;
+				)*
+				usages
+			}
+		}
+
+		$(
+			unsafe impl $crate::mem::buffer::usage::$usage for $id {}
+		)*
+	};
+}
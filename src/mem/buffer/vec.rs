@@ -9,15 +9,18 @@ use std::{
 };
 use ash::vk;
 use crate::{
+	OomError,
 	Device,
 	DeviceOwned,
 	mem::{
+		Slot,
 		self,
 		Allocator,
 		HostVisible,
 		buffer::{
 			self,
 			Usages,
+			usage,
 			Unbound,
 			Bound
 		},
@@ -71,27 +74,27 @@ impl From<mem::Error> for Error {
 	}
 }
 
-struct Inner<A: Allocator> {
-	buffer: Bound<HostVisible<A::Slot>>,
+struct Inner<A: Allocator, U: usage::Set> {
+	buffer: Bound<HostVisible<A::Slot>, U>,
 	capacity: u64
 }
 
-pub struct Vec<T, A: Allocator> {
+pub struct Vec<T, A: Allocator, U: usage::Set = Usages> {
 	allocator: staging::Allocator<A>,
-	usage: Usages,
+	usage: U,
 	sharing_mode: SharingQueues,
-	inner: Option<Inner<A>>,
+	inner: Option<Inner<A, U>>,
 	len: u64,
 	t: PhantomData<T>
 }
 
-impl<T, A: Allocator> Vec<T, A> {
+impl<T, A: Allocator, U: usage::Set> Vec<T, A, U> {
 	pub fn device(&self) -> &Arc<Device> {
 		self.allocator.device()
 	}
 
 	pub fn usage(&self) -> Usages {
-		self.usage
+		self.usage.usages()
 	}
 
 	pub fn capacity(&self) -> u64 {
@@ -103,15 +106,96 @@ impl<T, A: Allocator> Vec<T, A> {
 		}
 	}
 
+	pub fn len(&self) -> u64 {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Size in bytes of the elements of the vector.
+	fn byte_len(&self) -> u64 {
+		self.len * std::mem::size_of::<T>() as u64
+	}
+
+	/// Flushes the host writes, making them visible to the device.
+	///
+	/// This is done automatically before submitting a command buffer reading the vector,
+	/// and is a no-op if the memory is host coherent.
+	pub fn flush(&self) -> Result<(), OomError> {
+		match &self.inner {
+			Some(inner) => inner.buffer.memory_slot().flush(),
+			None => Ok(())
+		}
+	}
+
+	/// Invalidates the content of the vector, making the device writes visible to the host.
+	///
+	/// This must be called before reading data written by the device,
+	/// and is a no-op if the memory is host coherent.
+	pub fn invalidate(&self) -> Result<(), OomError> {
+		match &self.inner {
+			Some(inner) => inner.buffer.memory_slot().invalidate(0..self.byte_len()),
+			None => Ok(())
+		}
+	}
+
 	fn ptr(&self) -> *const T {
-		self.inner.as_ref().map(|inner| inner.buffer.memory_slot().ptr() as *const T).unwrap_or(std::ptr::null())
+		self.mut_ptr()
+	}
+
+	fn mut_ptr(&self) -> *mut T {
+		self.inner.as_ref().map(|inner| inner.buffer.memory_slot().ptr() as *mut T).unwrap_or(std::ptr::NonNull::dangling().as_ptr())
+	}
+
+	/// Marks the given range of elements as written by the host.
+	fn mark_dirty(&self, range: std::ops::Range<u64>) {
+		if let Some(inner) = &self.inner {
+			let size = std::mem::size_of::<T>() as u64;
+			inner.buffer.memory_slot().mark_dirty(range.start * size..range.end * size)
+		}
+	}
+
+	#[inline]
+	pub fn as_slice(&self) -> &[T] {
+		self
+	}
+
+	#[inline]
+	pub fn as_mut_slice(&mut self) -> &mut [T] {
+		self
+	}
+
+	#[inline]
+	pub fn iter(&self) -> std::slice::Iter<'_, T> {
+		self.as_slice().iter()
+	}
+
+	#[inline]
+	pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+		self.as_mut_slice().iter_mut()
+	}
+
+	/// Shortens the vector to the given length.
+	///
+	/// Has no effect if the vector is already shorter.
+	/// The capacity of the vector is unchanged.
+	pub fn truncate(&mut self, len: u64) {
+		if len < self.len {
+			self.len = len
+		}
 	}
 
-	fn mut_ptr(&mut self) -> *mut T {
-		self.inner.as_ref().map(|inner| inner.buffer.memory_slot().ptr() as *mut T).unwrap_or(std::ptr::null_mut())
+	/// Removes all the elements of the vector.
+	///
+	/// The capacity of the vector is unchanged.
+	#[inline]
+	pub fn clear(&mut self) {
+		self.len = 0
 	}
 
-	pub fn into_typed(self) -> Result<buffer::Typed<T, HostVisible<A::Slot>>, Error> {
+	pub fn into_typed(self) -> Result<buffer::Typed<T, HostVisible<A::Slot>, U>, Error> {
 		match self.inner {
 			Some(inner) => {
 				Ok(unsafe { inner.buffer.into_typed() })
@@ -123,7 +207,7 @@ impl<T, A: Allocator> Vec<T, A> {
 				let buffer = Unbound::new(
 					self.device(),
 					0,
-					self.usage(),
+					self.usage,
 					self.sharing_mode.clone()
 				)?;
 
@@ -137,12 +221,12 @@ impl<T, A: Allocator> Vec<T, A> {
 	}
 }
 
-impl<T: Copy, A: Allocator> Vec<T, A> {
-	pub fn new<U: Into<Usages>, S: Into<SharingQueues>>(allocator: staging::Allocator<A>, initial_capacity: u64, usage: U, sharing_queues: S) -> Result<Self, Error> {
+impl<T: Copy, A: Allocator, U: usage::Set> Vec<T, A, U> {
+	pub fn new<S: Into<SharingQueues>>(allocator: staging::Allocator<A>, initial_capacity: u64, usage: U, sharing_queues: S) -> Result<Self, Error> {
 		log::info!("new vec");
 		let mut this = Self {
 			allocator,
-			usage: usage.into(),
+			usage,
 			sharing_mode: sharing_queues.into(),
 			inner: None,
 			len: 0,
@@ -152,6 +236,17 @@ impl<T: Copy, A: Allocator> Vec<T, A> {
 		this.ensure_capacity(initial_capacity)?;
 		Ok(this)
 	}
+
+	/// Creates a vector holding the items of the given iterator.
+	///
+	/// `FromIterator` cannot be implemented instead,
+	/// since the vector needs an allocator to be created.
+	pub fn from_iter_in<I: IntoIterator<Item = T>, S: Into<SharingQueues>>(allocator: staging::Allocator<A>, iter: I, usage: U, sharing_queues: S) -> Result<Self, Error> {
+		let iter = iter.into_iter();
+		let mut this = Self::new(allocator, iter.size_hint().0 as u64, usage, sharing_queues)?;
+		this.extend(iter)?;
+		Ok(this)
+	}
 	
 	fn ensure_capacity(&mut self, capacity: u64) -> Result<(), Error> {
 		if capacity > self.capacity() {
@@ -166,39 +261,81 @@ impl<T: Copy, A: Allocator> Vec<T, A> {
 				}
 			}
 
-			let layout = std::alloc::Layout::new::<T>();
+			self.set_capacity(new_capacity)?;
+		}
+
+		Ok(())
+	}
 
-			let new_buffer = Unbound::new(
-				self.device(),
-				new_capacity * layout.size() as u64,
-				self.usage(),
-				self.sharing_mode.clone()
-			)?;
+	/// Reallocates the buffer to hold exactly `new_capacity` elements.
+	///
+	/// The content of the vector is preserved up to the new capacity.
+	/// On error, the vector is left unchanged.
+	fn set_capacity(&mut self, new_capacity: u64) -> Result<(), Error> {
+		if new_capacity == 0 {
+			self.inner = None;
+			self.len = 0;
+			return Ok(())
+		}
 
-			let memory_requirements = new_buffer.memory_requirements().align_to(layout.align() as u64);
-	
-			let new_slot = match self.inner.take() {
-				Some(inner) => {
-					self.allocator.reallocate(inner.buffer.unbind(), memory_requirements)?
-				},
-				None => {
-					self.allocator.allocate(memory_requirements)?
+		let layout = std::alloc::Layout::new::<T>();
+
+		let new_buffer = Unbound::new(
+			self.device(),
+			new_capacity * layout.size() as u64,
+			self.usage,
+			self.sharing_mode.clone()
+		)?;
+
+		let memory_requirements = new_buffer.memory_requirements().align_to(layout.align() as u64);
+
+		// The current buffer is kept until the new one is bound,
+		// so that the vector is left untouched on error.
+		let new_slot = self.allocator.allocate(memory_requirements)?;
+		let bound_buffer = unsafe {
+			match new_buffer.bind(new_slot) {
+				Ok(bound_buffer) => bound_buffer,
+				Err((_, e)) => {
+					return Err(e.into())
 				}
-			};
-
-			let bound_buffer = unsafe {
-				match new_buffer.bind(new_slot) {
-					Ok(bound_buffer) => bound_buffer,
-					Err((_, e)) => {
-						return Err(e.into())
-					}
-				}
-			};
-	
-			self.inner = Some(Inner {
-				buffer: bound_buffer,
-				capacity: new_capacity,
-			});
+			}
+		};
+
+		let len = std::cmp::min(self.len, new_capacity);
+		if let Some(inner) = self.inner.take() {
+			let byte_len = len * layout.size() as u64;
+			let new_slot = bound_buffer.memory_slot();
+			unsafe {
+				std::ptr::copy_nonoverlapping(
+					inner.buffer.memory_slot().ptr() as *const u8,
+					new_slot.ptr() as *mut u8,
+					byte_len as usize
+				)
+			}
+			new_slot.mark_dirty(0..byte_len)
+		}
+
+		self.len = len;
+		self.inner = Some(Inner {
+			buffer: bound_buffer,
+			capacity: new_capacity,
+		});
+
+		Ok(())
+	}
+
+	/// Reserves capacity for at least `additional` more elements.
+	#[inline]
+	pub fn reserve(&mut self, additional: u64) -> Result<(), Error> {
+		self.ensure_capacity(self.len + additional)
+	}
+
+	/// Shrinks the capacity of the vector to its length.
+	///
+	/// The buffer is released if the vector is empty.
+	pub fn shrink_to_fit(&mut self) -> Result<(), Error> {
+		if self.len < self.capacity() {
+			self.set_capacity(self.len)?
 		}
 
 		Ok(())
@@ -207,29 +344,140 @@ impl<T: Copy, A: Allocator> Vec<T, A> {
 	pub fn resize(&mut self, new_len: u64, value: T) -> Result<(), Error> {
 		self.ensure_capacity(new_len)?;
 
-		self.len = new_len;
-		for i in self.len..new_len {
-			self[i as usize] = value;
+		let old_len = self.len;
+		if new_len > old_len {
+			let ptr = self.mut_ptr();
+			for i in old_len..new_len {
+				unsafe { ptr.add(i as usize).write(value) }
+			}
+
+			self.mark_dirty(old_len..new_len)
 		}
 
+		self.len = new_len;
 		Ok(())
 	}
 
 	pub fn push(&mut self, value: T) -> Result<(), Error> {
-		let i = self.len as usize;
+		self.ensure_capacity(self.len + 1)?;
+
+		let i = self.len;
+		unsafe { self.mut_ptr().add(i as usize).write(value) }
+		self.mark_dirty(i..(i + 1));
 		self.len += 1;
 
-		self.ensure_capacity(self.len)?;
+		Ok(())
+	}
+
+	/// Removes the last element of the vector and returns it, or `None` if it is empty.
+	pub fn pop(&mut self) -> Option<T> {
+		if self.len == 0 {
+			None
+		} else {
+			self.len -= 1;
+			Some(unsafe { self.ptr().add(self.len as usize).read() })
+		}
+	}
+
+	/// Inserts an element at the given index, shifting all the elements after it.
+	///
+	/// ## Panics
+	///
+	/// This function panics if `index > len`.
+	pub fn insert(&mut self, index: u64, value: T) -> Result<(), Error> {
+		assert!(index <= self.len, "insertion index (is {}) should be <= len (is {})", index, self.len);
+		self.ensure_capacity(self.len + 1)?;
 
-		self[i] = value;
+		let old_len = self.len;
+		unsafe {
+			let ptr = self.mut_ptr().add(index as usize);
+			std::ptr::copy(ptr, ptr.add(1), (old_len - index) as usize);
+			ptr.write(value)
+		}
+		self.mark_dirty(index..(old_len + 1));
+		self.len += 1;
 
 		Ok(())
 	}
+
+	/// Removes and returns the element at the given index, shifting all the elements after it.
+	///
+	/// ## Panics
+	///
+	/// This function panics if `index >= len`.
+	pub fn remove(&mut self, index: u64) -> T {
+		assert!(index < self.len, "removal index (is {}) should be < len (is {})", index, self.len);
+
+		let value = unsafe {
+			let ptr = self.mut_ptr().add(index as usize);
+			let value = ptr.read();
+			std::ptr::copy(ptr.add(1), ptr, (self.len - index - 1) as usize);
+			value
+		};
+		self.mark_dirty(index..(self.len - 1));
+		self.len -= 1;
+
+		value
+	}
+
+	/// Appends all the elements of the given slice.
+	pub fn extend_from_slice(&mut self, values: &[T]) -> Result<(), Error> {
+		self.ensure_capacity(self.len + values.len() as u64)?;
+
+		let old_len = self.len;
+		unsafe {
+			std::ptr::copy_nonoverlapping(values.as_ptr(), self.mut_ptr().add(old_len as usize), values.len())
+		}
+		self.len += values.len() as u64;
+		self.mark_dirty(old_len..self.len);
+
+		Ok(())
+	}
+
+	/// Appends all the elements of the given iterator.
+	pub fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), Error> {
+		let iter = iter.into_iter();
+		self.reserve(iter.size_hint().0 as u64)?;
+
+		let old_len = self.len;
+		for value in iter {
+			if let Err(e) = self.ensure_capacity(self.len + 1) {
+				self.mark_dirty(old_len..self.len);
+				return Err(e)
+			}
+
+			unsafe { self.mut_ptr().add(self.len as usize).write(value) }
+			self.len += 1
+		}
+
+		self.mark_dirty(old_len..self.len);
+		Ok(())
+	}
+}
+
+impl<'a, T, A: Allocator, U: usage::Set> IntoIterator for &'a Vec<T, A, U> {
+	type Item = &'a T;
+	type IntoIter = std::slice::Iter<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
 }
 
-impl<T, A: Allocator> Deref for Vec<T, A> {
+impl<'a, T, A: Allocator, U: usage::Set> IntoIterator for &'a mut Vec<T, A, U> {
+	type Item = &'a mut T;
+	type IntoIter = std::slice::IterMut<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter_mut()
+	}
+}
+
+impl<T, A: Allocator, U: usage::Set> Deref for Vec<T, A, U> {
 	type Target = [T];
 
+	/// Device writes are not made visible by this function,
+	/// see `invalidate`.
 	fn deref(&self) -> &[T] {
 		unsafe {
 			std::slice::from_raw_parts(self.ptr(), self.len as usize)
@@ -237,8 +485,12 @@ impl<T, A: Allocator> Deref for Vec<T, A> {
 	}
 }
 
-impl<T, A: Allocator> DerefMut for Vec<T, A> {
+impl<T, A: Allocator, U: usage::Set> DerefMut for Vec<T, A, U> {
+	/// The content is marked as written by the host,
+	/// and will be flushed before the next submission reading it.
 	fn deref_mut(&mut self) -> &mut [T] {
+		self.mark_dirty(0..self.len);
+
 		unsafe {
 			std::slice::from_raw_parts_mut(self.mut_ptr(), self.len as usize)
 		}
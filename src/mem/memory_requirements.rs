@@ -1,21 +1,104 @@
-use ash::vk;
-use crate::instance::{
-	PhysicalDevice,
-	physical_device::MemoryType
+use ash::{
+	vk,
+	version::DeviceV1_0
 };
+use crate::{
+	Device,
+	mem::MemoryUsage,
+	instance::{
+		PhysicalDevice,
+		physical_device::MemoryType
+	}
+};
+
+/// Resource whose memory requirements are described.
+#[derive(Clone, Copy)]
+pub(crate) enum Resource {
+	Buffer(vk::Buffer),
+	Image(vk::Image)
+}
 
+#[derive(Clone, Copy)]
 pub struct MemoryRequirements {
 	vulkan: vk::MemoryRequirements,
-	linear: bool
+	linear: bool,
+	resource: Resource,
+	prefers_dedicated: bool,
+	requires_dedicated: bool,
+	usage: Option<MemoryUsage>
 }
 
 impl MemoryRequirements {
 	#[inline]
-	pub(crate) fn new(vulkan: vk::MemoryRequirements, linear: bool) -> Self {
+	pub(crate) fn new(vulkan: vk::MemoryRequirements, linear: bool, resource: Resource) -> Self {
 		Self {
 			vulkan,
-			linear
+			linear,
+			resource,
+			prefers_dedicated: false,
+			requires_dedicated: false,
+			usage: None
+		}
+	}
+
+	/// Queries the memory requirements of a resource.
+	///
+	/// If the `VK_KHR_get_memory_requirements2` and `VK_KHR_dedicated_allocation` extensions are enabled,
+	/// the dedicated allocation preferences of the resource are also queried.
+	pub(crate) fn query(device: &Device, resource: Resource, linear: bool) -> Self {
+		if device.loaded_extensions().khr_dedicated_allocation {
+			if let Ok(ext) = device.ext_khr_get_memory_requirements2() {
+				let mut dedicated = vk::MemoryDedicatedRequirements::default();
+				let mut mr = vk::MemoryRequirements2 {
+					p_next: &mut dedicated as *mut _ as *mut std::ffi::c_void,
+					..Default::default()
+				};
+
+				unsafe {
+					match resource {
+						Resource::Buffer(buffer) => {
+							let info = vk::BufferMemoryRequirementsInfo2 {
+								buffer,
+								..Default::default()
+							};
+
+							ext.get_buffer_memory_requirements2_khr(device.handle.handle(), &info, &mut mr);
+						},
+						Resource::Image(image) => {
+							let info = vk::ImageMemoryRequirementsInfo2 {
+								image,
+								..Default::default()
+							};
+
+							ext.get_image_memory_requirements2_khr(device.handle.handle(), &info, &mut mr);
+						}
+					}
+				}
+
+				return Self {
+					vulkan: mr.memory_requirements,
+					linear,
+					resource,
+					prefers_dedicated: dedicated.prefers_dedicated_allocation != vk::FALSE,
+					requires_dedicated: dedicated.requires_dedicated_allocation != vk::FALSE,
+					usage: None
+				}
+			}
 		}
+
+		let mr = unsafe {
+			match resource {
+				Resource::Buffer(buffer) => device.handle.get_buffer_memory_requirements(buffer),
+				Resource::Image(image) => device.handle.get_image_memory_requirements(image)
+			}
+		};
+
+		Self::new(mr, linear, resource)
+	}
+
+	#[inline]
+	pub(crate) fn resource(&self) -> Resource {
+		self.resource
 	}
 
 	#[inline]
@@ -47,7 +130,19 @@ impl MemoryRequirements {
 				alignment: std::cmp::max(self.alignment(), align), // works because alignemnt values are powers of two.
 				memory_type_bits: self.memory_type_bits()
 			},
-			linear: self.linear
+			..*self
+		}
+	}
+
+	/// Creates new memory requirements with the same alignment and memory types but a different size.
+	#[inline]
+	pub(crate) fn resized(&self, size: u64) -> MemoryRequirements {
+		MemoryRequirements {
+			vulkan: vk::MemoryRequirements {
+				size,
+				..self.vulkan
+			},
+			..*self
 		}
 	}
 
@@ -66,6 +161,49 @@ impl MemoryRequirements {
 		self.linear
 	}
 
+	/// Returns true if the implementation prefers the resource to have its own dedicated memory allocation.
+	///
+	/// This is always `false` unless the `VK_KHR_dedicated_allocation` extension is enabled.
+	#[inline]
+	pub fn prefers_dedicated(&self) -> bool {
+		self.prefers_dedicated
+	}
+
+	/// Returns true if the resource must have its own dedicated memory allocation.
+	///
+	/// Dedicated memory can be allocated using `Device::allocate_dedicated_memory`.
+	#[inline]
+	pub fn requires_dedicated(&self) -> bool {
+		self.requires_dedicated
+	}
+
+	/// Returns the intended usage of the memory, if any.
+	#[inline]
+	pub fn usage(&self) -> Option<MemoryUsage> {
+		self.usage
+	}
+
+	/// Creates new memory requirements with the given intended usage.
+	///
+	/// Allocators use it to select the most appropriate memory type.
+	#[inline]
+	pub fn with_usage(&self, usage: MemoryUsage) -> MemoryRequirements {
+		MemoryRequirements {
+			usage: Some(usage),
+			..*self
+		}
+	}
+
+	/// Returns the memory types allowed by the requirements, from the most to the least appropriate.
+	///
+	/// If no usage is specified, memory types are returned in the order given by the device.
+	pub fn memory_types<'a>(&self, physical_device: PhysicalDevice<'a>) -> Vec<MemoryType<'a>> {
+		match self.usage {
+			Some(usage) => usage.memory_types(physical_device, self.memory_type_bits()),
+			None => physical_device.memory_types().filter(|memory_type| memory_type.satisfies(self)).collect()
+		}
+	}
+
 	#[inline]
 	pub fn contains_memory_type_index(&self, index: u32) -> bool {
 		self.vulkan.memory_type_bits & (1u32 << index) != 0
@@ -95,7 +233,7 @@ impl MemoryRequirements {
 				alignment: self.alignment(),
 				memory_type_bits: new_memory_type_bits
 			},
-			linear: self.linear
+			..*self
 		}
 	}
 
@@ -1,25 +1,44 @@
 use std::{
 	ffi::c_void,
+	ops::Range,
+	sync::Arc,
 	fmt
 };
+use ash::{
+	vk,
+	version::DeviceV1_0
+};
+use parking_lot::Mutex;
 use crate::{
+	OomError,
 	DeviceOwned,
 	device
 };
 
 pub mod buffer;
-mod memory_requirements;
+pub(crate) mod memory_requirements;
 pub mod staging;
+pub mod bounded;
+pub mod ring;
+pub mod sparse;
+pub mod stats;
+pub mod unbounded;
+mod usage;
 
 // pub use buffer::{
 // 	// Buffers,
 // 	LocalBuffers
 // };
 pub use memory_requirements::MemoryRequirements;
+pub use unbounded::Unbounded;
+pub use bounded::Bounded;
+pub use ring::Ring;
+pub use usage::MemoryUsage;
 
 #[derive(Debug)]
 pub enum Error {
 	OutOfMemory,
+	NoSuitableMemoryType,
 	Map(device::memory::MapError),
 	Allocation(device::AllocationError)
 }
@@ -28,6 +47,7 @@ impl fmt::Display for Error {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
 			Error::OutOfMemory => write!(f, "out of memory."),
+			Error::NoSuitableMemoryType => write!(f, "no suitable memory type."),
 			Error::Map(e) => write!(f, "map error: {}", e),
 			Error::Allocation(e) => write!(f, "allocation error: {}", e)
 		}
@@ -38,6 +58,7 @@ impl std::error::Error for Error {
 	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
 		match self {
 			Error::OutOfMemory => None,
+			Error::NoSuitableMemoryType => None,
 			Error::Map(e) => Some(e),
 			Error::Allocation(e) => Some(e)
 		}
@@ -56,6 +77,61 @@ impl From<device::AllocationError> for Error {
 	}
 }
 
+/// Typed view error.
+#[derive(Debug)]
+pub enum ViewError {
+	/// The viewed range is not included in the slot.
+	OutOfBounds,
+
+	/// The viewed range is not aligned on the alignment of the view item type.
+	Misaligned
+}
+
+impl fmt::Display for ViewError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ViewError::OutOfBounds => write!(f, "view out of bounds."),
+			ViewError::Misaligned => write!(f, "misaligned view.")
+		}
+	}
+}
+
+impl std::error::Error for ViewError {}
+
+/// Plain old data.
+///
+/// Types that can be viewed in host-visible memory.
+///
+/// ## Safety
+///
+/// The type must not contain any padding byte, pointer or reference,
+/// and any bit pattern must be a valid value of the type.
+pub unsafe trait Pod: 'static + Copy {}
+
+macro_rules! pod {
+	($($ty:ty),*) => {
+		$(unsafe impl Pod for $ty {})*
+	}
+}
+
+pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Device memory allocation hook.
+///
+/// Called by the allocators supporting it (see `Allocator::set_memory_hook`)
+/// each time they allocate or free a block of device memory.
+pub trait MemoryHook: Send + Sync {
+	/// Called before allocating `size` bytes of device memory in the given heap.
+	///
+	/// The allocation is aborted if an error is returned.
+	fn allocate(&self, heap: u32, size: u64) -> Result<(), Error>;
+
+	/// Called after `size` bytes of device memory have been freed in the given heap.
+	fn free(&self, heap: u32, size: u64);
+}
+
 pub unsafe trait Allocator: 'static + DeviceOwned {
 	type Slot: Slot;
 
@@ -76,6 +152,15 @@ pub unsafe trait Allocator: 'static + DeviceOwned {
 
 	/// Reallocate host-visible memory.
 	fn reallocate(&self, slot: HostVisible<Self::Slot>, memory_requirements: MemoryRequirements) -> Result<HostVisible<Self::Slot>, Error>;
+
+	/// Sets the hook called each time the allocator allocates or frees device memory.
+	///
+	/// Only the memory allocated after this call is reported to the hook.
+	/// Returns `false` if the allocator does not support hooks.
+	fn set_memory_hook(&self, hook: Arc<dyn MemoryHook>) -> bool {
+		let _ = hook;
+		false
+	}
 }
 
 unsafe impl<A: 'static + std::ops::Deref + DeviceOwned> Allocator for A where A::Target: Allocator {
@@ -94,6 +179,10 @@ unsafe impl<A: 'static + std::ops::Deref + DeviceOwned> Allocator for A where A:
 	fn reallocate(&self, slot: HostVisible<Self::Slot>, memory_requirements: MemoryRequirements) -> Result<HostVisible<Self::Slot>, Error> {
 		std::ops::Deref::deref(self).reallocate(slot, memory_requirements)
 	}
+
+	fn set_memory_hook(&self, hook: Arc<dyn MemoryHook>) -> bool {
+		std::ops::Deref::deref(self).set_memory_hook(hook)
+	}
 }
 
 pub unsafe trait Slot: 'static {
@@ -104,6 +193,22 @@ pub unsafe trait Slot: 'static {
 	fn size(&self) -> u64;
 
 	fn ptr(&self) -> Option<*mut c_void>;
+
+	/// Marks the given range of the slot (relative to its offset) as written by the host.
+	///
+	/// Written ranges are only tracked by host-visible slots in non-coherent memory.
+	#[inline]
+	fn mark_dirty(&self, _range: Range<u64>) {
+		// nothing to track.
+	}
+
+	/// Host writes tracker of the slot.
+	///
+	/// Only host-visible slots in non-coherent memory have one.
+	#[inline]
+	fn host_writes(&self) -> Option<&Arc<HostWrites>> {
+		None
+	}
 }
 
 unsafe impl<S: 'static + Slot + ?Sized> Slot for Box<S> {
@@ -122,49 +227,287 @@ unsafe impl<S: 'static + Slot + ?Sized> Slot for Box<S> {
 	fn ptr(&self) -> Option<*mut c_void> {
 		self.as_ref().ptr()
 	}
+
+	fn mark_dirty(&self, range: Range<u64>) {
+		self.as_ref().mark_dirty(range)
+	}
+
+	fn host_writes(&self) -> Option<&Arc<HostWrites>> {
+		self.as_ref().host_writes()
+	}
+}
+
+/// Host writes to a non-coherent memory slot.
+///
+/// Shared between the slot and the command buffers reading it,
+/// which flush the written range when submitted,
+/// including the writes made after the command buffer has been recorded.
+pub struct HostWrites {
+	memory: vk::DeviceMemory,
+
+	/// Offset of the slot in the memory.
+	offset: u64,
+
+	/// Size of the slot.
+	size: u64,
+
+	/// The `nonCoherentAtomSize` limit of the device.
+	atom_size: u64,
+
+	/// Range written by the host and not yet flushed, relative to the slot offset.
+	dirty: Mutex<Option<Range<u64>>>
+}
+
+impl HostWrites {
+	fn new<S: Slot + ?Sized>(slot: &S, dirty: Option<Range<u64>>) -> HostWrites {
+		HostWrites {
+			memory: slot.memory().handle(),
+			offset: slot.offset(),
+			size: slot.size(),
+			atom_size: slot.memory().device().physical_device().limits().non_coherent_atom_size(),
+			dirty: Mutex::new(dirty)
+		}
+	}
+
+	/// Marks the given range of the slot as written by the host.
+	fn mark(&self, range: Range<u64>) {
+		if range.start < range.end {
+			let mut dirty = self.dirty.lock();
+			*dirty = match dirty.take() {
+				Some(current) => Some(std::cmp::min(current.start, range.start)..std::cmp::max(current.end, range.end)),
+				None => Some(range)
+			}
+		}
+	}
+
+	/// Takes the memory range written by the host since the last flush.
+	///
+	/// Returns `None` if there is nothing to flush.
+	pub(crate) fn take(&self) -> Option<vk::MappedMemoryRange> {
+		let range = self.dirty.lock().take()?;
+		self.mapped_range(range)
+	}
+
+	/// Returns the memory range to flush or invalidate in order to cover the given range of the slot.
+	///
+	/// The range is extended to the `nonCoherentAtomSize` limit of the device,
+	/// without exceeding the slot.
+	/// Allocators align the slots of non-coherent memory types to this limit,
+	/// so that the range never covers other slots.
+	fn mapped_range(&self, range: Range<u64>) -> Option<vk::MappedMemoryRange> {
+		let start = range.start / self.atom_size * self.atom_size;
+		let end = std::cmp::min((range.end + self.atom_size - 1) / self.atom_size * self.atom_size, self.size);
+		if start < end {
+			Some(vk::MappedMemoryRange {
+				memory: self.memory,
+				offset: self.offset + start,
+				size: end - start,
+				..Default::default()
+			})
+		} else {
+			None
+		}
+	}
 }
 
-pub struct HostVisible<S: Slot>(S);
+/// Host-visible memory slot.
+///
+/// If the slot is allocated in a memory type that is not host coherent,
+/// host writes must be flushed before being visible to the device,
+/// and device writes must be invalidated before being visible to the host.
+/// The ranges written by the host are tracked using `mark_dirty` so that
+/// only those are flushed.
+/// Command buffers reading this slot flush its written ranges
+/// before being submitted (see `HostWrites`).
+pub struct HostVisible<S: Slot> {
+	inner: S,
+
+	/// Host writes, tracked only if the memory is not host coherent.
+	writes: Option<Arc<HostWrites>>
+}
 
 impl<S: Slot> HostVisible<S> {
 	#[inline]
 	pub fn try_from(s: S) -> Result<Self, S> {
 		if s.ptr().is_some() {
-			Ok(HostVisible(s))
+			Ok(Self::from_raw_parts(s, None))
 		} else {
 			Err(s)
 		}
 	}
 
+	/// Creates a host-visible slot with the given unflushed range.
+	///
+	/// The slot must be mapped.
+	#[inline]
+	pub(crate) fn from_raw_parts(s: S, dirty: Option<Range<u64>>) -> Self {
+		debug_assert!(s.ptr().is_some());
+		let writes = if s.memory().memory_type().is_host_coherent() {
+			None
+		} else {
+			Some(Arc::new(HostWrites::new(&s, dirty)))
+		};
+
+		HostVisible {
+			inner: s,
+			writes
+		}
+	}
+
+	/// Returns the underlying slot along with its unflushed range.
+	#[inline]
+	pub(crate) fn into_raw_parts(self) -> (S, Option<Range<u64>>) {
+		let dirty = self.writes.and_then(|writes| writes.dirty.lock().take());
+		(self.inner, dirty)
+	}
+
 	#[inline]
 	pub fn ptr(&self) -> *mut c_void {
-		self.0.ptr().unwrap()
+		self.inner.ptr().unwrap()
+	}
+
+	/// Checks if the slot is allocated in host coherent memory.
+	///
+	/// If it is, flushing and invalidating are no-ops.
+	#[inline]
+	pub fn is_coherent(&self) -> bool {
+		self.writes.is_none()
+	}
+
+	/// Flushes the ranges written by the host, making them visible to the device.
+	pub fn flush(&self) -> Result<(), OomError> {
+		if let Some(range) = self.writes.as_ref().and_then(|writes| writes.take()) {
+			unsafe {
+				self.memory().device().handle().flush_mapped_memory_ranges(&[range])?
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Invalidates the given range of the slot (relative to its offset),
+	/// making the device writes visible to the host.
+	///
+	/// Pending host writes are flushed first so that they are not discarded.
+	pub fn invalidate(&self, range: Range<u64>) -> Result<(), OomError> {
+		if let Some(writes) = &self.writes {
+			if let Some(range) = writes.mapped_range(range) {
+				self.flush()?;
+				unsafe {
+					self.memory().device().handle().invalidate_mapped_memory_ranges(&[range])?
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns a pointer to the `len` items of type `T` starting at the given byte offset in the slot.
+	fn view_ptr<T>(&self, offset: u64, len: usize) -> Result<*mut T, ViewError> {
+		let byte_len = (len as u64).checked_mul(std::mem::size_of::<T>() as u64).ok_or(ViewError::OutOfBounds)?;
+		match offset.checked_add(byte_len) {
+			Some(end) if end <= self.inner.size() => (),
+			_ => return Err(ViewError::OutOfBounds)
+		}
+
+		let ptr = unsafe { (self.ptr() as *mut u8).offset(offset as isize) };
+		if ptr as usize % std::mem::align_of::<T>() != 0 {
+			return Err(ViewError::Misaligned)
+		}
+
+		Ok(ptr as *mut T)
 	}
 
+	/// Returns a view of the `len` items of type `T` starting at the given byte offset in the slot.
+	///
+	/// Device writes in the viewed range are invalidated first if the memory is not host coherent.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the invalidation fails.
+	pub fn view<T: Pod>(&self, offset: u64, len: usize) -> Result<&[T], ViewError> {
+		let ptr = self.view_ptr::<T>(offset, len)?;
+		self.invalidate(offset..(offset + (len * std::mem::size_of::<T>()) as u64)).expect("unable to invalidate host memory");
+		Ok(unsafe { std::slice::from_raw_parts(ptr, len) })
+	}
+
+	/// Returns a mutable view of the `len` items of type `T` starting at the given byte offset in the slot.
+	///
+	/// The viewed range is marked as written by the host.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the invalidation of the range fails.
+	pub fn view_mut<T: Pod>(&mut self, offset: u64, len: usize) -> Result<&mut [T], ViewError> {
+		let ptr = self.view_ptr::<T>(offset, len)?;
+		let range = offset..(offset + (len * std::mem::size_of::<T>()) as u64);
+		self.invalidate(range.clone()).expect("unable to invalidate host memory");
+		self.mark_dirty(range);
+		Ok(unsafe { std::slice::from_raw_parts_mut(ptr, len) })
+	}
+
+	/// Returns a view of the whole slot as a slice of `T`.
+	///
+	/// Trailing bytes not filling a whole item are ignored.
+	#[inline]
+	pub fn as_slice<T: Pod>(&self) -> Result<&[T], ViewError> {
+		self.view(0, self.item_count::<T>())
+	}
+
+	/// Returns a mutable view of the whole slot as a slice of `T`.
+	///
+	/// Trailing bytes not filling a whole item are ignored.
+	#[inline]
+	pub fn as_mut_slice<T: Pod>(&mut self) -> Result<&mut [T], ViewError> {
+		self.view_mut(0, self.item_count::<T>())
+	}
+
+	#[inline]
+	fn item_count<T>(&self) -> usize {
+		match std::mem::size_of::<T>() {
+			0 => 0,
+			size => (self.inner.size() / size as u64) as usize
+		}
+	}
+
+	/// Returns the underlying slot.
+	///
+	/// Ranges written by the host and not yet flushed are lost.
 	#[inline]
 	pub fn unwrap(self) -> S {
-		self.0
+		self.inner
 	}
 }
 
 unsafe impl<S: Slot> Slot for HostVisible<S> {
 	#[inline]
 	fn memory(&self) -> &device::Memory {
-		self.0.memory()
+		self.inner.memory()
 	}
 
 	#[inline]
 	fn offset(&self) -> u64 {
-		self.0.offset()
+		self.inner.offset()
 	}
 
 	#[inline]
 	fn size(&self) -> u64 {
-		self.0.size()
+		self.inner.size()
 	}
 
 	#[inline]
 	fn ptr(&self) -> Option<*mut c_void> {
-		self.0.ptr()
+		self.inner.ptr()
 	}
-}
\ No newline at end of file
+
+	fn mark_dirty(&self, range: Range<u64>) {
+		if let Some(writes) = &self.writes {
+			writes.mark(range)
+		}
+	}
+
+	#[inline]
+	fn host_writes(&self) -> Option<&Arc<HostWrites>> {
+		self.writes.as_ref()
+	}
+}
@@ -0,0 +1,451 @@
+use std::{
+	collections::VecDeque,
+	sync::Arc,
+	fmt
+};
+use ash::vk;
+use crate::{
+	OomError,
+	Device,
+	DeviceOwned,
+	resource,
+	sync::{
+		future::SignalFence,
+		SharingQueues
+	}
+};
+use super::{
+	Allocator,
+	HostVisible,
+	HostWrites,
+	MemoryUsage,
+	Pod,
+	Slot,
+	ViewError,
+	buffer::{
+		self,
+		Usages,
+		usage,
+		Unbound,
+		Bound
+	}
+};
+
+#[derive(Debug)]
+pub enum Error {
+	BufferCreation(buffer::CreationError),
+	Bind(buffer::BindError),
+	Memory(super::Error),
+
+	/// The requested range does not fit in a newly chained buffer.
+	RangeTooLarge(u64)
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::BufferCreation(e) => write!(f, "buffer creation failed: {}", e),
+			Error::Bind(e) => write!(f, "bind failed: {}", e),
+			Error::Memory(e) => write!(f, "memory error: {}", e),
+			Error::RangeTooLarge(size) => write!(f, "range of {} bytes does not fit in a ring buffer", size)
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+		match self {
+			Error::BufferCreation(e) => Some(e),
+			Error::Bind(e) => Some(e),
+			Error::Memory(e) => Some(e),
+			Error::RangeTooLarge(_) => None
+		}
+	}
+}
+
+impl From<buffer::CreationError> for Error {
+	fn from(e: buffer::CreationError) -> Self {
+		Self::BufferCreation(e)
+	}
+}
+
+impl From<buffer::BindError> for Error {
+	fn from(e: buffer::BindError) -> Self {
+		Self::Bind(e)
+	}
+}
+
+impl From<super::Error> for Error {
+	fn from(e: super::Error) -> Self {
+		Self::Memory(e)
+	}
+}
+
+#[inline]
+fn align_up(offset: u64, alignment: u64) -> u64 {
+	(offset + alignment - 1) / alignment * alignment
+}
+
+/// Host-visible buffer from which ring ranges are allocated.
+struct Chunk<S: Slot, U: usage::Set> {
+	buffer: Bound<HostVisible<S>, U>
+}
+
+impl<S: Slot, U: usage::Set> Chunk<S, U> {
+	#[inline]
+	fn size(&self) -> u64 {
+		self.buffer.memory_slot().size()
+	}
+}
+
+/// Allocation state of a chunk.
+///
+/// The used part of the chunk is the range going from `tail` to `head`,
+/// possibly wrapping around the end of the chunk.
+struct ChunkState<S: Slot, U: usage::Set> {
+	chunk: Arc<Chunk<S, U>>,
+
+	/// Unique identifier of the chunk in the ring.
+	id: u64,
+
+	/// Start of the oldest range still in use.
+	tail: u64,
+
+	/// End of the last allocated range.
+	head: u64,
+
+	/// Number of pending frames that allocated ranges in this chunk.
+	frames: usize,
+
+	/// Has the current frame allocated ranges in this chunk.
+	touched: bool
+}
+
+impl<S: Slot, U: usage::Set> ChunkState<S, U> {
+	#[inline]
+	fn is_empty(&self) -> bool {
+		self.frames == 0 && !self.touched
+	}
+
+	/// Finds room for `size` bytes aligned on `alignment`.
+	fn allocate(&mut self, size: u64, alignment: u64) -> Option<u64> {
+		let capacity = self.chunk.size();
+
+		let offset = if self.is_empty() {
+			self.tail = 0;
+			if size <= capacity {
+				0
+			} else {
+				return None
+			}
+		} else if self.head > self.tail || (self.head == self.tail && self.tail == capacity) {
+			let offset = align_up(self.head, alignment);
+			if offset + size <= capacity {
+				offset
+			} else if size <= self.tail {
+				0
+			} else {
+				return None
+			}
+		} else if self.head < self.tail {
+			let offset = align_up(self.head, alignment);
+			if offset + size <= self.tail {
+				offset
+			} else {
+				return None
+			}
+		} else {
+			// full.
+			return None
+		};
+
+		self.head = offset + size;
+		self.touched = true;
+		Some(offset)
+	}
+}
+
+/// Frame submitted to the device, whose ranges are reclaimed once its fence is signaled.
+struct Frame {
+	future: Box<dyn Send + Sync + SignalFence>,
+
+	/// Chunks used by the frame, with the position of the head at the end of the frame.
+	ends: Vec<(u64, u64)>
+}
+
+/// Ring allocator for transient data.
+///
+/// Hands out ranges of host-visible buffers to data that changes every frame,
+/// such as uniform data, dynamic vertices or upload staging.
+/// Ranges allocated during a frame are reclaimed once the fence
+/// passed to `end_frame` is signaled.
+/// When the ring is full, a new buffer twice as big is chained to it,
+/// and the previous buffers are released once all their ranges are reclaimed.
+///
+/// The buffers are created with the usages `U`,
+/// which must include `usage::TransferSource` to copy ranges to other buffers.
+///
+/// If the buffers are not in host coherent memory, host writes to the ranges
+/// must be flushed (see `Ring::flush` and `Range::flush`) before the device reads them,
+/// unless they are read by commands recorded with a `Recorder`,
+/// which are flushed when the command buffer is submitted.
+pub struct Ring<A: Allocator, U: usage::Set = Usages> {
+	allocator: A,
+	usage: U,
+	sharing_queues: SharingQueues,
+
+	/// Chunks, the last one being the current chunk.
+	chunks: Vec<ChunkState<A::Slot, U>>,
+
+	/// Identifier of the next chunk.
+	next_id: u64,
+
+	/// Pending frames, from the oldest to the newest.
+	frames: VecDeque<Frame>
+}
+
+impl<A: Allocator, U: usage::Set> Ring<A, U> {
+	/// Creates a new ring allocator with an initial buffer of `capacity` bytes.
+	pub fn new<S: Into<SharingQueues>>(allocator: A, capacity: u64, usage: U, sharing_queues: S) -> Result<Self, Error> {
+		let mut ring = Self {
+			allocator,
+			usage,
+			sharing_queues: sharing_queues.into(),
+			chunks: Vec::new(),
+			next_id: 0,
+			frames: VecDeque::new()
+		};
+
+		ring.chain(capacity)?;
+		Ok(ring)
+	}
+
+	pub fn usage(&self) -> Usages {
+		self.usage.usages()
+	}
+
+	/// Total capacity of the chained buffers, in bytes.
+	pub fn capacity(&self) -> u64 {
+		self.chunks.iter().map(|c| c.chunk.size()).sum()
+	}
+
+	/// Number of frames whose ranges are not yet reclaimed.
+	pub fn pending_frames(&self) -> usize {
+		self.frames.len()
+	}
+
+	/// Chains a new buffer of the given capacity.
+	fn chain(&mut self, capacity: u64) -> Result<(), Error> {
+		let buffer = Unbound::new(self.allocator.device(), capacity, self.usage, self.sharing_queues.clone())?;
+		let memory_requirements = buffer.memory_requirements()
+			.host_visible(self.allocator.device().physical_device())
+			.with_usage(MemoryUsage::Dynamic);
+
+		let slot = HostVisible::try_from(self.allocator.allocate(memory_requirements)?).map_err(|_| super::Error::NoSuitableMemoryType)?;
+		let buffer = unsafe {
+			buffer.bind(slot).map_err(|(_, e)| e)?
+		};
+
+		self.chunks.push(ChunkState {
+			chunk: Arc::new(Chunk {
+				buffer
+			}),
+			id: self.next_id,
+			tail: 0,
+			head: 0,
+			frames: 0,
+			touched: false
+		});
+
+		self.next_id += 1;
+		Ok(())
+	}
+
+	/// Reclaims the ranges of the frames whose fence is signaled.
+	///
+	/// Chained buffers other than the current one are released once empty.
+	pub fn reclaim(&mut self) {
+		while let Some(frame) = self.frames.front() {
+			match frame.future.is_signaled() {
+				Ok(true) => (),
+				_ => break
+			}
+
+			let frame = self.frames.pop_front().unwrap();
+			for (id, end) in frame.ends {
+				if let Some(state) = self.chunks.iter_mut().find(|c| c.id == id) {
+					state.tail = end;
+					state.frames -= 1;
+				}
+			}
+		}
+
+		let last = self.chunks.len() - 1;
+		let mut i = 0;
+		self.chunks.retain(|state| {
+			let keep = i == last || !state.is_empty();
+			i += 1;
+			keep
+		})
+	}
+
+	/// Allocates `size` bytes aligned on `alignment` (which must be a power of two).
+	///
+	/// The returned range is valid until the end of the current frame
+	/// and must not be used by the host or the device after the frame's fence is signaled.
+	pub fn allocate(&mut self, size: u64, alignment: u64) -> Result<Range<A::Slot, U>, Error> {
+		debug_assert!(alignment.is_power_of_two());
+		let size = std::cmp::max(size, 1);
+
+		let state = self.chunks.last_mut().unwrap();
+		let offset = match state.allocate(size, alignment) {
+			Some(offset) => offset,
+			None => {
+				self.reclaim();
+				let state = self.chunks.last_mut().unwrap();
+				match state.allocate(size, alignment) {
+					Some(offset) => offset,
+					None => {
+						let capacity = std::cmp::max(state.chunk.size() * 2, size);
+						self.chain(capacity)?;
+						self.chunks.last_mut().unwrap().allocate(size, alignment).ok_or(Error::RangeTooLarge(size))?
+					}
+				}
+			}
+		};
+
+		Ok(Range {
+			chunk: self.chunks.last().unwrap().chunk.clone(),
+			offset,
+			size
+		})
+	}
+
+	/// Flushes the host writes to the ranges allocated during the current frame,
+	/// making them visible to the device.
+	///
+	/// This must be called before submitting the commands of the frame
+	/// reading the ranges through descriptor sets or raw commands.
+	pub fn flush(&self) -> Result<(), OomError> {
+		for state in &self.chunks {
+			if state.touched {
+				state.chunk.buffer.memory_slot().flush()?
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Ends the current frame.
+	///
+	/// The ranges allocated since the previous call are reclaimed once the given future is signaled.
+	pub fn end_frame<F: 'static + Send + Sync + SignalFence>(&mut self, future: F) {
+		let mut ends = Vec::new();
+		for state in &mut self.chunks {
+			if state.touched {
+				state.touched = false;
+				state.frames += 1;
+				ends.push((state.id, state.head))
+			}
+		}
+
+		self.frames.push_back(Frame {
+			future: Box::new(future),
+			ends
+		});
+
+		self.reclaim()
+	}
+}
+
+impl<A: Allocator, U: usage::Set> DeviceOwned for Ring<A, U> {
+	fn device(&self) -> &Arc<Device> {
+		self.allocator.device()
+	}
+}
+
+/// Range of a buffer allocated by a `Ring` allocator.
+pub struct Range<S: Slot, U: usage::Set = Usages> {
+	chunk: Arc<Chunk<S, U>>,
+	offset: u64,
+	size: u64
+}
+
+impl<S: Slot, U: usage::Set> Range<S, U> {
+	/// Pointer to the beginning of the range.
+	#[inline]
+	pub fn ptr(&self) -> *mut std::ffi::c_void {
+		unsafe { (self.chunk.buffer.memory_slot().ptr() as *mut u8).offset(self.offset as isize) as *mut std::ffi::c_void }
+	}
+
+	/// Returns a mutable view of the range as a slice of `T`.
+	///
+	/// Trailing bytes not filling a whole item are ignored.
+	/// The range is marked as written by the host,
+	/// and must be flushed before being read by the device (see `flush`).
+	///
+	/// ## Safety
+	///
+	/// The range must not be accessed after the end of the frame it was allocated in:
+	/// once the frame is reclaimed, the same bytes may be handed out to another range,
+	/// and the returned slice would alias it.
+	pub unsafe fn as_mut_slice<T: Pod>(&mut self) -> Result<&mut [T], ViewError> {
+		let len = match std::mem::size_of::<T>() {
+			0 => 0,
+			item_size => (self.size / item_size as u64) as usize
+		};
+
+		let slot = self.chunk.buffer.memory_slot();
+		let ptr = slot.view_ptr::<T>(self.offset, len)?;
+		slot.mark_dirty(self.offset..(self.offset + self.size));
+		Ok(std::slice::from_raw_parts_mut(ptr, len))
+	}
+
+	/// Flushes the host writes to the range, making them visible to the device.
+	///
+	/// Other ranges of the same buffer may be flushed as well.
+	#[inline]
+	pub fn flush(&self) -> Result<(), OomError> {
+		self.chunk.buffer.memory_slot().flush()
+	}
+
+	/// Copies the given data at the beginning of the range.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the data does not fit in the range or is misaligned.
+	///
+	/// ## Safety
+	///
+	/// The range must not be accessed after the end of the frame it was allocated in
+	/// (see `as_mut_slice`).
+	pub unsafe fn write<T: Pod>(&mut self, data: &[T]) {
+		self.as_mut_slice::<T>().expect("invalid ring range view")[..data.len()].copy_from_slice(data)
+	}
+}
+
+unsafe impl<S: Slot, U: usage::Set> resource::Reference for Range<S, U> {
+	type Handle = vk::Buffer;
+
+	fn handle(&self) -> vk::Buffer {
+		resource::Reference::handle(&self.chunk.buffer)
+	}
+}
+
+unsafe impl<S: Slot, U: usage::Set> buffer::sub::Read for Range<S, U> {
+	type Usage = U;
+
+	fn byte_offset(&self) -> u64 {
+		self.offset
+	}
+
+	fn byte_len(&self) -> u64 {
+		self.size
+	}
+
+	fn host_writes(&self) -> Option<Arc<HostWrites>> {
+		self.chunk.buffer.memory_slot().host_writes().cloned()
+	}
+}
+
+unsafe impl<S: Slot, U: usage::Set> buffer::sub::Write for Range<S, U> {}
@@ -0,0 +1,638 @@
+use std::{
+	collections::HashMap,
+	ops::Range,
+	sync::Arc
+};
+use ash::{
+	vk,
+	version::DeviceV1_0
+};
+use crate::{
+	Device,
+	DeviceOwned,
+	image,
+	resource,
+	sync::task
+};
+use super::{
+	Slot,
+	MemoryRequirements,
+	buffer::{
+		self,
+		Usages,
+		usage
+	}
+};
+
+/// Sparse resource creation flags.
+///
+/// Sparse resources are not bound to a single memory slot,
+/// but page by page (tile by tile for images) with `Queue::bind_sparse`.
+/// Creating a sparse resource requires the `sparse_binding` feature.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Flags {
+	/// The resource may be partially resident.
+	///
+	/// Requires the `sparse_residency_*` feature matching the resource.
+	pub residency: bool,
+
+	/// Pages of the resource may alias memory bound to other pages or resources.
+	///
+	/// Requires the `sparse_residency_aliased` feature.
+	pub aliased: bool
+}
+
+impl Flags {
+	/// Sparse binding only: the whole resource must be bound before it is used.
+	#[inline]
+	pub fn binding() -> Self {
+		Self::default()
+	}
+
+	/// Partially resident resource.
+	#[inline]
+	pub fn residency() -> Self {
+		Self {
+			residency: true,
+			aliased: false
+		}
+	}
+
+	pub(crate) fn buffer_create_flags(&self) -> vk::BufferCreateFlags {
+		let mut flags = vk::BufferCreateFlags::SPARSE_BINDING;
+
+		if self.residency {
+			flags |= vk::BufferCreateFlags::SPARSE_RESIDENCY
+		}
+
+		if self.aliased {
+			flags |= vk::BufferCreateFlags::SPARSE_ALIASED
+		}
+
+		flags
+	}
+
+	pub(crate) fn image_create_flags(&self) -> vk::ImageCreateFlags {
+		let mut flags = vk::ImageCreateFlags::SPARSE_BINDING;
+
+		if self.residency {
+			flags |= vk::ImageCreateFlags::SPARSE_RESIDENCY
+		}
+
+		if self.aliased {
+			flags |= vk::ImageCreateFlags::SPARSE_ALIASED
+		}
+
+		flags
+	}
+}
+
+/// Tile of a sparse image.
+///
+/// Tiles are obtained with `Image::tile`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Tile {
+	aspect_mask: vk::ImageAspectFlags,
+	mip_level: u32,
+	array_layer: u32,
+	offset: (u32, u32, u32),
+	extent: (u32, u32, u32)
+}
+
+impl Tile {
+	#[inline]
+	pub fn aspects(&self) -> image::view::Aspects {
+		image::view::Aspects::from_vulkan(self.aspect_mask)
+	}
+
+	#[inline]
+	pub fn mip_level(&self) -> u32 {
+		self.mip_level
+	}
+
+	#[inline]
+	pub fn array_layer(&self) -> u32 {
+		self.array_layer
+	}
+
+	/// Offset of the tile in the mip level, in texels.
+	#[inline]
+	pub fn offset(&self) -> (u32, u32, u32) {
+		self.offset
+	}
+
+	/// Extent of the tile, in texels.
+	///
+	/// Tiles on the edge of the mip level may be smaller than the image granularity.
+	#[inline]
+	pub fn extent(&self) -> (u32, u32, u32) {
+		self.extent
+	}
+
+	fn bind_info(&self, memory: vk::DeviceMemory, memory_offset: u64) -> vk::SparseImageMemoryBind {
+		vk::SparseImageMemoryBind {
+			subresource: vk::ImageSubresource {
+				aspect_mask: self.aspect_mask,
+				mip_level: self.mip_level,
+				array_layer: self.array_layer
+			},
+			offset: vk::Offset3D {
+				x: self.offset.0 as i32,
+				y: self.offset.1 as i32,
+				z: self.offset.2 as i32
+			},
+			extent: vk::Extent3D {
+				width: self.extent.0,
+				height: self.extent.1,
+				depth: self.extent.2
+			},
+			memory,
+			memory_offset,
+			flags: vk::SparseMemoryBindFlags::empty()
+		}
+	}
+}
+
+/// Sparse memory requirements of some aspects of an image.
+#[derive(Clone, Copy)]
+pub struct ImageMemoryRequirements {
+	vulkan: vk::SparseImageMemoryRequirements
+}
+
+impl ImageMemoryRequirements {
+	/// Aspects of the image described by these requirements.
+	#[inline]
+	pub fn aspects(&self) -> image::view::Aspects {
+		image::view::Aspects::from_vulkan(self.vulkan.format_properties.aspect_mask)
+	}
+
+	/// Size of a tile, in texels.
+	#[inline]
+	pub fn granularity(&self) -> (u32, u32, u32) {
+		let g = self.vulkan.format_properties.image_granularity;
+		(g.width, g.height, g.depth)
+	}
+
+	/// First mip level of the mip tail.
+	///
+	/// Mip levels from this one are not bound tile by tile,
+	/// but as an opaque memory range (see `mip_tail`).
+	#[inline]
+	pub fn mip_tail_first_lod(&self) -> u32 {
+		self.vulkan.image_mip_tail_first_lod
+	}
+
+	/// Checks if all the array layers share a single mip tail.
+	#[inline]
+	pub fn has_single_mip_tail(&self) -> bool {
+		self.vulkan.format_properties.flags.contains(vk::SparseImageFormatFlags::SINGLE_MIPTAIL)
+	}
+
+	/// Opaque memory range of the mip tail of the given array layer, if any.
+	pub fn mip_tail(&self, array_layer: u32) -> Option<Range<u64>> {
+		if self.vulkan.image_mip_tail_size == 0 {
+			return None
+		}
+
+		let offset = if self.has_single_mip_tail() {
+			self.vulkan.image_mip_tail_offset
+		} else {
+			self.vulkan.image_mip_tail_offset + array_layer as u64 * self.vulkan.image_mip_tail_stride
+		};
+
+		Some(offset..(offset + self.vulkan.image_mip_tail_size))
+	}
+
+	#[inline]
+	fn covers(&self, aspect_mask: vk::ImageAspectFlags) -> bool {
+		self.vulkan.format_properties.aspect_mask.contains(aspect_mask)
+	}
+}
+
+/// Memory slots bound to the pages of a sparse resource.
+pub struct PageTable<S: Slot> {
+	/// Opaque bindings, indexed by resource offset, with their size.
+	opaque: HashMap<u64, (u64, S)>,
+
+	/// Image tile bindings.
+	tiles: HashMap<Tile, S>
+}
+
+impl<S: Slot> PageTable<S> {
+	fn new() -> Self {
+		Self {
+			opaque: HashMap::new(),
+			tiles: HashMap::new()
+		}
+	}
+
+	/// Number of bound pages and tiles.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.opaque.len() + self.tiles.len()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Slot bound at the given resource offset, if any.
+	#[inline]
+	pub fn opaque(&self, offset: u64) -> Option<&S> {
+		self.opaque.get(&offset).map(|(_, slot)| slot)
+	}
+
+	/// Slot bound to the given tile, if any.
+	#[inline]
+	pub fn tile(&self, tile: &Tile) -> Option<&S> {
+		self.tiles.get(tile)
+	}
+
+	/// Opaque memory ranges bound, with their slot.
+	pub fn opaque_ranges(&self) -> impl '_ + Iterator<Item=(Range<u64>, &S)> {
+		self.opaque.iter().map(|(offset, (size, slot))| (*offset..(*offset + *size), slot))
+	}
+
+	/// Tiles bound, with their slot.
+	pub fn tiles(&self) -> impl '_ + Iterator<Item=(&Tile, &S)> {
+		self.tiles.iter()
+	}
+
+	/// Applies the given update, returning the previously bound slot.
+	pub(crate) fn apply(&mut self, update: Update<S>) -> Option<S> {
+		match update {
+			Update::Opaque(range, slot) => self.replace_opaque(range, slot),
+			Update::Tile(tile, slot) => self.replace_tile(tile, slot)
+		}
+	}
+
+	fn replace_opaque(&mut self, range: Range<u64>, slot: Option<S>) -> Option<S> {
+		debug_assert!(self.opaque.iter().all(|(offset, (size, _))| {
+			(*offset == range.start && *offset + *size == range.end) || *offset >= range.end || *offset + *size <= range.start
+		}), "opaque sparse binding partially overlapping another one");
+
+		let size = range.end - range.start;
+		match slot {
+			Some(slot) => self.opaque.insert(range.start, (size, slot)),
+			None => self.opaque.remove(&range.start)
+		}.map(|(_, slot)| slot)
+	}
+
+	fn replace_tile(&mut self, tile: Tile, slot: Option<S>) -> Option<S> {
+		match slot {
+			Some(slot) => self.tiles.insert(tile, slot),
+			None => self.tiles.remove(&tile)
+		}
+	}
+}
+
+/// Page table update of a sparse binding operation.
+///
+/// Updates are only applied once the operation is submitted.
+pub(crate) enum Update<S: Slot> {
+	Opaque(Range<u64>, Option<S>),
+	Tile(Tile, Option<S>)
+}
+
+fn memory_bind<S: Slot>(range: Range<u64>, slot: Option<&S>) -> vk::SparseMemoryBind {
+	let (memory, memory_offset) = match slot {
+		Some(slot) => {
+			debug_assert!(slot.size() >= range.end - range.start);
+			(slot.memory().handle(), slot.offset())
+		},
+		None => (vk::DeviceMemory::null(), 0)
+	};
+
+	vk::SparseMemoryBind {
+		resource_offset: range.start,
+		size: range.end - range.start,
+		memory,
+		memory_offset,
+		flags: vk::SparseMemoryBindFlags::empty()
+	}
+}
+
+/// Sparse buffer.
+///
+/// Memory is bound page by page with `Queue::bind_sparse`.
+/// Slots bound to the buffer are released with it.
+pub struct Buffer<S: Slot, U: usage::Set = Usages> {
+	inner: buffer::Unbound<U>,
+	memory_requirements: MemoryRequirements,
+	pages: PageTable<S>
+}
+
+impl<S: Slot, U: usage::Set> Buffer<S, U> {
+	/// Wraps a buffer created with `buffer::Unbound::new_sparse`.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the buffer is not sparse.
+	pub fn new(inner: buffer::Unbound<U>) -> Self {
+		assert!(inner.sparse().is_some(), "not a sparse buffer");
+		let memory_requirements = inner.memory_requirements();
+
+		Self {
+			inner,
+			memory_requirements,
+			pages: PageTable::new()
+		}
+	}
+
+	#[inline]
+	pub fn len(&self) -> u64 {
+		self.inner.len()
+	}
+
+	#[inline]
+	pub fn flags(&self) -> Flags {
+		self.inner.sparse().unwrap()
+	}
+
+	/// Size of a page, in bytes.
+	#[inline]
+	pub fn page_size(&self) -> u64 {
+		self.memory_requirements.alignment()
+	}
+
+	/// Number of pages covering the buffer.
+	#[inline]
+	pub fn page_count(&self) -> u64 {
+		(self.memory_requirements.size() + self.page_size() - 1) / self.page_size()
+	}
+
+	/// Memory requirements of a single page.
+	#[inline]
+	pub fn page_memory_requirements(&self) -> MemoryRequirements {
+		self.memory_requirements.resized(self.page_size())
+	}
+
+	#[inline]
+	pub fn pages(&self) -> &PageTable<S> {
+		&self.pages
+	}
+
+	/// Checks if the given page is bound to some memory.
+	#[inline]
+	pub fn is_resident(&self, page: u64) -> bool {
+		self.pages.opaque(page * self.page_size()).is_some()
+	}
+
+	/// Binds (or unbinds) a page, returning the Vulkan binding and the page table update to apply once it is submitted.
+	pub(crate) fn bind_page(&self, page: u64, slot: Option<S>) -> (vk::SparseMemoryBind, Update<S>) {
+		debug_assert!(page < self.page_count());
+		let offset = page * self.page_size();
+		let range = offset..std::cmp::min(offset + self.page_size(), self.memory_requirements.size());
+
+		let bind = memory_bind(range.clone(), slot.as_ref());
+		(bind, Update::Opaque(range, slot))
+	}
+
+	#[inline]
+	pub(crate) fn pages_mut(&mut self) -> &mut PageTable<S> {
+		&mut self.pages
+	}
+}
+
+impl<S: Slot, U: usage::Set> DeviceOwned for Buffer<S, U> {
+	fn device(&self) -> &Arc<Device> {
+		self.inner.device()
+	}
+}
+
+unsafe impl<S: Slot, U: usage::Set> resource::Reference for Buffer<S, U> {
+	type Handle = vk::Buffer;
+
+	fn handle(&self) -> vk::Buffer {
+		self.inner.handle()
+	}
+}
+
+unsafe impl<S: Slot, U: usage::Set> buffer::sub::Read for Buffer<S, U> {
+	type Usage = U;
+
+	fn byte_offset(&self) -> u64 {
+		0
+	}
+
+	fn byte_len(&self) -> u64 {
+		self.inner.len()
+	}
+}
+
+unsafe impl<S: Slot, U: usage::Set> buffer::sub::Write for Buffer<S, U> {}
+
+/// Sparse image.
+///
+/// If the image is partially resident, memory is bound tile by tile with `Queue::bind_sparse`,
+/// and the mip tail as an opaque memory range.
+/// Otherwise the whole image memory is bound as opaque memory ranges.
+/// Slots bound to the image are released with it.
+pub struct Image<S: Slot> {
+	inner: image::Unbound,
+	memory_requirements: MemoryRequirements,
+	requirements: Vec<ImageMemoryRequirements>,
+	pages: PageTable<S>
+}
+
+impl<S: Slot> Image<S> {
+	/// Wraps an image created with `image::Unbound::new_sparse`.
+	///
+	/// ## Panics
+	///
+	/// This function panics if the image is not sparse.
+	pub fn new(inner: image::Unbound) -> Self {
+		assert!(inner.sparse().is_some(), "not a sparse image");
+		let memory_requirements = inner.memory_requirements();
+
+		let requirements = unsafe {
+			let device = inner.device().handle();
+			let fp = device.fp_v1_0();
+
+			let mut count = 0;
+			fp.get_image_sparse_memory_requirements(device.handle(), inner.handle(), &mut count, std::ptr::null_mut());
+			let mut vulkan = vec![vk::SparseImageMemoryRequirements::default(); count as usize];
+			fp.get_image_sparse_memory_requirements(device.handle(), inner.handle(), &mut count, vulkan.as_mut_ptr());
+			vulkan.set_len(count as usize);
+
+			vulkan.into_iter().map(|vulkan| ImageMemoryRequirements { vulkan }).collect()
+		};
+
+		Self {
+			inner,
+			memory_requirements,
+			requirements,
+			pages: PageTable::new()
+		}
+	}
+
+	#[inline]
+	pub fn flags(&self) -> Flags {
+		self.inner.sparse().unwrap()
+	}
+
+	#[inline]
+	pub fn size(&self) -> (u32, u32, u32) {
+		self.inner.size()
+	}
+
+	/// Sparse memory requirements of each aspect of the image.
+	#[inline]
+	pub fn requirements(&self) -> &[ImageMemoryRequirements] {
+		&self.requirements
+	}
+
+	/// Size of the opaque memory of the image, in bytes.
+	#[inline]
+	pub fn opaque_size(&self) -> u64 {
+		self.memory_requirements.size()
+	}
+
+	/// Size of a page (or tile), in bytes.
+	#[inline]
+	pub fn page_size(&self) -> u64 {
+		self.memory_requirements.alignment()
+	}
+
+	/// Memory requirements of a single page (or tile).
+	#[inline]
+	pub fn page_memory_requirements(&self) -> MemoryRequirements {
+		self.memory_requirements.resized(self.page_size())
+	}
+
+	#[inline]
+	pub fn pages(&self) -> &PageTable<S> {
+		&self.pages
+	}
+
+	fn requirements_of(&self, aspects: image::view::Aspects) -> Option<&ImageMemoryRequirements> {
+		let aspect_mask = aspects.into_vulkan();
+		self.requirements.iter().find(|r| r.covers(aspect_mask))
+	}
+
+	/// Number of tiles in each dimension of the given mip level.
+	///
+	/// Returns `None` if the mip level is in the mip tail,
+	/// or if the image has no sparse requirements for the given aspects.
+	pub fn tile_count(&self, aspects: image::view::Aspects, mip_level: u32) -> Option<(u32, u32, u32)> {
+		let requirements = self.requirements_of(aspects)?;
+		if mip_level >= requirements.mip_tail_first_lod() || mip_level >= self.inner.mip_levels() {
+			return None
+		}
+
+		let (width, height, depth) = mip_extent(self.inner.size(), mip_level);
+		let (gw, gh, gd) = requirements.granularity();
+		Some(((width + gw - 1) / gw, (height + gh - 1) / gh, (depth + gd - 1) / gd))
+	}
+
+	/// Returns the tile of the given aspects at the given tile coordinates.
+	///
+	/// Returns `None` if the coordinates are out of the mip level bounds,
+	/// or if the mip level is in the mip tail.
+	pub fn tile(&self, aspects: image::view::Aspects, mip_level: u32, array_layer: u32, coordinates: (u32, u32, u32)) -> Option<Tile> {
+		let (cx, cy, cz) = self.tile_count(aspects, mip_level)?;
+		let (x, y, z) = coordinates;
+		if x >= cx || y >= cy || z >= cz || array_layer >= self.inner.array_layers() {
+			return None
+		}
+
+		let (width, height, depth) = mip_extent(self.inner.size(), mip_level);
+		let (gw, gh, gd) = self.requirements_of(aspects)?.granularity();
+		let offset = (x * gw, y * gh, z * gd);
+
+		Some(Tile {
+			aspect_mask: aspects.into_vulkan(),
+			mip_level,
+			array_layer,
+			offset,
+			extent: (
+				std::cmp::min(gw, width - offset.0),
+				std::cmp::min(gh, height - offset.1),
+				std::cmp::min(gd, depth - offset.2)
+			)
+		})
+	}
+
+	/// Opaque memory range of the mip tail of the given aspects and array layer, if any.
+	#[inline]
+	pub fn mip_tail(&self, aspects: image::view::Aspects, array_layer: u32) -> Option<Range<u64>> {
+		self.requirements_of(aspects)?.mip_tail(array_layer)
+	}
+
+	/// Checks if the given tile is bound to some memory.
+	#[inline]
+	pub fn is_resident(&self, tile: &Tile) -> bool {
+		self.pages.tile(tile).is_some()
+	}
+
+	/// Binds (or unbinds) a tile, returning the Vulkan binding and the page table update to apply once it is submitted.
+	pub(crate) fn bind_tile(&self, tile: Tile, slot: Option<S>) -> (vk::SparseImageMemoryBind, Update<S>) {
+		debug_assert!(self.flags().residency);
+		let bind = match &slot {
+			Some(slot) => {
+				debug_assert!(slot.size() >= self.page_size());
+				tile.bind_info(slot.memory().handle(), slot.offset())
+			},
+			None => tile.bind_info(vk::DeviceMemory::null(), 0)
+		};
+
+		(bind, Update::Tile(tile, slot))
+	}
+
+	/// Binds (or unbinds) an opaque memory range, returning the Vulkan binding and the page table update to apply once it is submitted.
+	pub(crate) fn bind_opaque(&self, range: Range<u64>, slot: Option<S>) -> (vk::SparseMemoryBind, Update<S>) {
+		debug_assert!(range.start % self.page_size() == 0);
+		let bind = memory_bind(range.clone(), slot.as_ref());
+		(bind, Update::Opaque(range, slot))
+	}
+
+	#[inline]
+	pub(crate) fn pages_mut(&mut self) -> &mut PageTable<S> {
+		&mut self.pages
+	}
+}
+
+fn mip_extent((width, height, depth): (u32, u32, u32), mip_level: u32) -> (u32, u32, u32) {
+	(
+		std::cmp::max(width >> mip_level, 1),
+		std::cmp::max(height >> mip_level, 1),
+		std::cmp::max(depth >> mip_level, 1)
+	)
+}
+
+impl<S: Slot> DeviceOwned for Image<S> {
+	fn device(&self) -> &Arc<Device> {
+		self.inner.device()
+	}
+}
+
+unsafe impl<S: Slot> resource::Reference for Image<S> {
+	type Handle = vk::Image;
+
+	fn handle(&self) -> vk::Image {
+		self.inner.handle()
+	}
+}
+
+unsafe impl<S: Slot> image::Image for Image<S> {
+	// ...
+}
+
+/// Memory slots unbound by a sparse binding operation.
+///
+/// They are released once the operation is complete.
+pub struct Released<S: Slot>(Vec<S>);
+
+impl<S: Slot> Released<S> {
+	pub(crate) fn new(slots: Vec<S>) -> Self {
+		Self(slots)
+	}
+
+	#[inline]
+	pub fn slots(&self) -> &[S] {
+		&self.0
+	}
+}
+
+unsafe impl<S: Slot> task::Payload for Released<S> {}
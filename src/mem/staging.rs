@@ -6,7 +6,9 @@ use crate::{
 use super::{
 	Error,
 	MemoryRequirements,
-	HostVisible
+	MemoryUsage,
+	HostVisible,
+	stats
 };
 
 /// Allocator only allocating memory to host visible memory.
@@ -23,8 +25,15 @@ impl<A: super::Allocator> Allocator<A> {
 		}
 	}
 
+	/// Restricts the memory requirements to host visible memory types.
+	///
+	/// If no usage is specified, the memory is assumed to be used for uploads (`MemoryUsage::Upload`).
 	pub fn filtered_memory_requirements(&self, memory_requirements: MemoryRequirements) -> MemoryRequirements {
-		memory_requirements.filter_memory_types(self.device().physical_device(), |memory_type| memory_type.is_host_visible())
+		let memory_requirements = memory_requirements.filter_memory_types(self.device().physical_device(), |memory_type| memory_type.is_host_visible());
+		match memory_requirements.usage() {
+			Some(_) => memory_requirements,
+			None => memory_requirements.with_usage(MemoryUsage::Upload)
+		}
 	}
 
 	pub fn allocate(&self, memory_requirements: MemoryRequirements) -> Result<HostVisible<A::Slot>, Error> {
@@ -50,6 +59,20 @@ unsafe impl<A: super::Allocator> super::Allocator for Allocator<A> {
 	fn reallocate(&self, slot: HostVisible<Self::Slot>, memory_requirements: MemoryRequirements) -> Result<HostVisible<Self::Slot>, Error> {
 		self.allocator.reallocate(slot, memory_requirements)
 	}
+
+	fn set_memory_hook(&self, hook: Arc<dyn super::MemoryHook>) -> bool {
+		self.allocator.set_memory_hook(hook)
+	}
+}
+
+impl<A: super::Allocator + stats::Inspect> stats::Inspect for Allocator<A> {
+	fn dump(&self) -> stats::Dump {
+		self.allocator.dump()
+	}
+
+	fn statistics(&self) -> stats::Statistics {
+		self.allocator.statistics()
+	}
 }
 
 impl<A: super::Allocator> DeviceOwned for Allocator<A> {
@@ -0,0 +1,225 @@
+use std::{
+	ops::Deref,
+	fmt
+};
+use crate::{
+	Device,
+	DeviceOwned,
+	device::HeapBudget
+};
+
+/// Slot allocated in a block.
+#[derive(Clone, Copy, Debug)]
+pub struct SlotDump {
+	/// Offset of the slot in the block.
+	pub offset: u64,
+
+	/// Size of the slot.
+	pub size: u64,
+
+	/// Is the slot used by a linear resource.
+	pub linear: bool
+}
+
+/// Range of a block that can still be allocated.
+#[derive(Clone, Copy, Debug)]
+pub struct FreeRange {
+	/// Offset of the range in the block.
+	pub offset: u64,
+
+	/// Size of the range.
+	pub size: u64
+}
+
+/// Memory block of an allocator.
+#[derive(Clone, Debug)]
+pub struct BlockDump {
+	/// Memory type index.
+	pub memory_type: u32,
+
+	/// Memory heap index.
+	pub heap: u32,
+
+	/// Size of the block.
+	pub size: u64,
+
+	/// Is the block mapped in host memory.
+	pub mapped: bool,
+
+	/// Is the block dedicated to a single resource.
+	pub dedicated: bool,
+
+	/// Slots allocated in the block, ordered by offset.
+	pub slots: Vec<SlotDump>,
+
+	/// Free ranges of the block, ordered by offset.
+	pub free_ranges: Vec<FreeRange>
+}
+
+/// Map of every block and slot of an allocator.
+#[derive(Clone, Debug, Default)]
+pub struct Dump {
+	pub blocks: Vec<BlockDump>
+}
+
+impl Dump {
+	/// Computes the statistics of the dumped allocator.
+	///
+	/// Heap budgets are included if the `VK_EXT_memory_budget` extension is enabled on the device.
+	pub fn statistics(&self, device: &Device) -> Statistics {
+		let physical_device = device.physical_device();
+		let budgets = device.memory_budget().ok();
+
+		let mut total = Usage::default();
+		let mut memory_types = vec![Usage::default(); physical_device.memory_type_count() as usize];
+		let mut heaps: Vec<_> = (0..physical_device.memory_heap_count() as usize).map(|i| {
+			HeapStatistics {
+				usage: Usage::default(),
+				budget: budgets.as_ref().map(|budgets| budgets[i])
+			}
+		}).collect();
+
+		for block in &self.blocks {
+			total.add(block);
+			memory_types[block.memory_type as usize].add(block);
+			heaps[block.heap as usize].usage.add(block);
+		}
+
+		Statistics {
+			total,
+			memory_types,
+			heaps
+		}
+	}
+
+	/// Writes the dump in JSON format.
+	pub fn write_json<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+		write!(w, "{{\"blocks\":[")?;
+		for (i, block) in self.blocks.iter().enumerate() {
+			if i > 0 {
+				write!(w, ",")?;
+			}
+
+			write!(w, "{{\"memory_type\":{},\"heap\":{},\"size\":{},\"mapped\":{},\"dedicated\":{},\"slots\":[", block.memory_type, block.heap, block.size, block.mapped, block.dedicated)?;
+			for (j, slot) in block.slots.iter().enumerate() {
+				if j > 0 {
+					write!(w, ",")?;
+				}
+
+				write!(w, "{{\"offset\":{},\"size\":{},\"linear\":{}}}", slot.offset, slot.size, slot.linear)?;
+			}
+
+			write!(w, "],\"free_ranges\":[")?;
+			for (j, range) in block.free_ranges.iter().enumerate() {
+				if j > 0 {
+					write!(w, ",")?;
+				}
+
+				write!(w, "{{\"offset\":{},\"size\":{}}}", range.offset, range.size)?;
+			}
+
+			write!(w, "]}}")?;
+		}
+
+		write!(w, "]}}")
+	}
+
+	/// Returns the dump in JSON format.
+	pub fn to_json(&self) -> String {
+		let mut json = String::new();
+		self.write_json(&mut json).unwrap();
+		json
+	}
+}
+
+/// Memory usage of an allocator.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Usage {
+	/// Number of memory blocks.
+	pub block_count: usize,
+
+	/// Number of allocated slots.
+	pub slot_count: usize,
+
+	/// Number of bytes of device memory allocated for the blocks.
+	pub block_bytes: u64,
+
+	/// Number of bytes allocated in slots.
+	pub allocated_bytes: u64,
+
+	/// Number of bytes that can still be allocated without allocating a new block.
+	pub free_bytes: u64,
+
+	/// Size of the largest free range.
+	pub largest_free_range: u64
+}
+
+impl Usage {
+	fn add(&mut self, block: &BlockDump) {
+		self.block_count += 1;
+		self.slot_count += block.slots.len();
+		self.block_bytes += block.size;
+		self.allocated_bytes += block.slots.iter().map(|slot| slot.size).sum::<u64>();
+
+		for range in &block.free_ranges {
+			self.free_bytes += range.size;
+			self.largest_free_range = std::cmp::max(self.largest_free_range, range.size)
+		}
+	}
+
+	/// Fragmentation of the free memory, between 0 and 1.
+	///
+	/// This is 0 when all the free memory is in a single range,
+	/// and gets closer to 1 as the free memory is split in small ranges.
+	pub fn fragmentation(&self) -> f32 {
+		if self.free_bytes == 0 {
+			0.0
+		} else {
+			1.0 - (self.largest_free_range as f64 / self.free_bytes as f64) as f32
+		}
+	}
+}
+
+/// Memory usage of an allocator in a given heap.
+#[derive(Clone, Copy, Debug)]
+pub struct HeapStatistics {
+	/// Usage of the heap by the allocator.
+	pub usage: Usage,
+
+	/// Budget of the heap, if `VK_EXT_memory_budget` is enabled.
+	pub budget: Option<HeapBudget>
+}
+
+/// Allocator statistics.
+#[derive(Clone, Debug)]
+pub struct Statistics {
+	/// Total usage.
+	pub total: Usage,
+
+	/// Usage per memory type.
+	pub memory_types: Vec<Usage>,
+
+	/// Usage per memory heap.
+	pub heaps: Vec<HeapStatistics>
+}
+
+/// Allocator that can be inspected.
+pub trait Inspect: DeviceOwned {
+	/// Returns the map of every block and slot of the allocator.
+	fn dump(&self) -> Dump;
+
+	/// Returns the allocator statistics.
+	fn statistics(&self) -> Statistics {
+		self.dump().statistics(self.device())
+	}
+}
+
+impl<A: Deref + DeviceOwned> Inspect for A where A::Target: Inspect {
+	fn dump(&self) -> Dump {
+		Deref::deref(self).dump()
+	}
+
+	fn statistics(&self) -> Statistics {
+		Deref::deref(self).statistics()
+	}
+}
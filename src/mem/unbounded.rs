@@ -1,10 +1,610 @@
+use std::{
+	ffi::c_void,
+	sync::{
+		Arc,
+		Weak
+	}
+};
+use parking_lot::Mutex;
+use crate::{
+	Device,
+	DeviceOwned,
+	device,
+	image,
+	instance::physical_device::MemoryType
+};
+use super::{
+	Error,
+	MemoryHook,
+	MemoryRequirements,
+	HostVisible,
+	buffer,
+	stats
+};
+
+mod linear;
+mod tlsf;
+pub mod defrag;
+
+use linear::Linear;
+use tlsf::Tlsf;
+pub use defrag::Defragmentation;
+
+/// Default size (in bytes) of the memory blocks allocated by the `Unbounded` allocator.
+pub const DEFAULT_BLOCK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Allocation strategy.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Strategy {
-	Linear
+	/// Slots are allocated one after the other in each memory block.
+	///
+	/// The space of a block is only reclaimed once every slot allocated in it has been released.
+	Linear,
+
+	/// Two-Level Segregated Fit.
+	///
+	/// General purpose strategy where slots are allocated in the free range that best fits them.
+	/// Released slots are coalesced with adjacent free ranges.
+	Tlsf
+}
+
+#[inline]
+fn align_up(offset: u64, alignment: u64) -> u64 {
+	(offset + alignment - 1) / alignment * alignment
+}
+
+/// Checks if a resource ending at `end` (excluded) and a resource starting at `start`
+/// share a page of the given granularity.
+#[inline]
+fn on_same_page(end: u64, start: u64, granularity: u64) -> bool {
+	end > 0 && (end - 1) / granularity == start / granularity
+}
+
+/// Memory of a block, persistently mapped if host visible.
+enum BlockMemory {
+	Unmapped(device::Memory),
+	Mapped(device::SharedMappedMemory)
+}
+
+impl BlockMemory {
+	#[inline]
+	fn memory(&self) -> &device::Memory {
+		match self {
+			Self::Unmapped(memory) => memory,
+			Self::Mapped(mapped) => mapped.memory()
+		}
+	}
+
+	#[inline]
+	fn ptr(&self) -> Option<*mut c_void> {
+		match self {
+			Self::Unmapped(_) => None,
+			Self::Mapped(mapped) => Some(mapped.ptr())
+		}
+	}
+}
+
+/// Allocation state of a block, depending on the allocator strategy.
+enum BlockState {
+	Linear(Linear),
+	Tlsf(Tlsf)
+}
+
+impl BlockState {
+	fn new(strategy: Strategy, size: u64, granularity: u64) -> Self {
+		match strategy {
+			Strategy::Linear => Self::Linear(Linear::new(size, granularity)),
+			Strategy::Tlsf => Self::Tlsf(Tlsf::new(size, granularity))
+		}
+	}
+
+	#[inline]
+	fn is_empty(&self) -> bool {
+		match self {
+			Self::Linear(l) => l.is_empty(),
+			Self::Tlsf(t) => t.is_empty()
+		}
+	}
+
+	#[inline]
+	fn allocate(&mut self, memory_requirements: &MemoryRequirements) -> Option<u64> {
+		let (size, alignment, linear) = (memory_requirements.size(), memory_requirements.alignment(), memory_requirements.is_linear());
+		match self {
+			Self::Linear(l) => l.allocate(size, alignment, linear),
+			Self::Tlsf(t) => t.allocate(size, alignment, linear)
+		}
+	}
+
+	#[inline]
+	fn resize(&mut self, offset: u64, memory_requirements: &MemoryRequirements) -> bool {
+		let (size, alignment, linear) = (memory_requirements.size(), memory_requirements.alignment(), memory_requirements.is_linear());
+		match self {
+			Self::Linear(l) => l.resize(offset, size, alignment, linear),
+			Self::Tlsf(t) => t.resize(offset, size, alignment, linear)
+		}
+	}
+
+	#[inline]
+	fn free(&mut self, offset: u64) {
+		match self {
+			Self::Linear(l) => l.free(offset),
+			Self::Tlsf(t) => t.free(offset)
+		}
+	}
+
+	#[inline]
+	fn dump(&self) -> (Vec<stats::SlotDump>, Vec<stats::FreeRange>) {
+		match self {
+			Self::Linear(l) => l.dump(),
+			Self::Tlsf(t) => t.dump()
+		}
+	}
+}
+
+/// Allocation of a block, as reported to the memory hook.
+///
+/// The hook is notified when this is dropped, after the block memory is freed.
+struct HookedAllocation {
+	hook: Arc<dyn MemoryHook>,
+	heap: u32,
+	size: u64
+}
+
+impl HookedAllocation {
+	/// Reports the allocation of `size` bytes in the given memory type to the hook, if any.
+	fn new(hook: Option<Arc<dyn MemoryHook>>, memory_type: &MemoryType, size: u64) -> Result<Option<Self>, Error> {
+		match hook {
+			Some(hook) => {
+				let heap = memory_type.heap_index();
+				hook.allocate(heap, size)?;
+				Ok(Some(Self {
+					hook,
+					heap,
+					size
+				}))
+			},
+			None => Ok(None)
+		}
+	}
+}
+
+impl Drop for HookedAllocation {
+	fn drop(&mut self) {
+		self.hook.free(self.heap, self.size)
+	}
+}
+
+/// Memory block from which slots are allocated.
+struct Block {
+	memory: BlockMemory,
+	state: Mutex<BlockState>,
+
+	/// Is the block dedicated to a single resource.
+	dedicated: bool,
+
+	/// Pool owning the block.
+	pool: Weak<Mutex<Pool>>,
+
+	/// Must be declared after `memory` so the hook is notified once the memory is freed.
+	_hooked: Option<HookedAllocation>
+}
+
+impl Block {
+	fn new(device: &Arc<Device>, memory_type: MemoryType, size: u64, state: BlockState, pool: Weak<Mutex<Pool>>, hook: Option<Arc<dyn MemoryHook>>) -> Result<Self, Error> {
+		let hooked = HookedAllocation::new(hook, &memory_type, size)?;
+		let memory = device.allocate_memory(memory_type, size)?;
+		Self::from_memory(memory, state, false, pool, hooked)
+	}
+
+	/// Creates a block dedicated to the resource described by the given memory requirements.
+	fn dedicated(device: &Arc<Device>, memory_type: MemoryType, memory_requirements: &MemoryRequirements, pool: Weak<Mutex<Pool>>, hook: Option<Arc<dyn MemoryHook>>) -> Result<Self, Error> {
+		let hooked = HookedAllocation::new(hook, &memory_type, memory_requirements.size())?;
+		let memory = device.allocate_dedicated_memory(memory_type, memory_requirements)?;
+		let state = BlockState::Linear(Linear::new(memory_requirements.size(), 1));
+		Self::from_memory(memory, state, true, pool, hooked)
+	}
+
+	fn from_memory(memory: device::Memory, state: BlockState, dedicated: bool, pool: Weak<Mutex<Pool>>, hooked: Option<HookedAllocation>) -> Result<Self, Error> {
+		let memory = if memory.memory_type().is_host_visible() {
+			BlockMemory::Mapped(device::SharedMappedMemory::new(memory)?)
+		} else {
+			BlockMemory::Unmapped(memory)
+		};
+
+		Ok(Self {
+			memory,
+			state: Mutex::new(state),
+			dedicated,
+			pool,
+			_hooked: hooked
+		})
+	}
+}
+
+/// Blocks of a given memory type.
+#[derive(Default)]
+struct Pool {
+	blocks: Vec<Arc<Block>>,
+
+	/// Number of bytes prepared with `Allocator::prepare` but not yet allocated.
+	prepared: u64
+}
+
+impl Pool {
+	/// Releases the given block if it is empty.
+	///
+	/// The last non-dedicated block of the pool is always kept to avoid repeatedly
+	/// allocating and freeing device memory.
+	fn release_if_empty(&mut self, block: &Arc<Block>) {
+		if (block.dedicated || self.blocks.iter().filter(|b| !b.dedicated).count() > 1) && block.state.lock().is_empty() {
+			self.blocks.retain(|b| !Arc::ptr_eq(b, block))
+		}
+	}
+}
+
+/// Memory slot allocated by the `Unbounded` allocator.
+pub struct Slot {
+	block: Arc<Block>,
+	offset: u64,
+	size: u64
+}
+
+unsafe impl super::Slot for Slot {
+	#[inline]
+	fn memory(&self) -> &device::Memory {
+		self.block.memory.memory()
+	}
+
+	#[inline]
+	fn offset(&self) -> u64 {
+		self.offset
+	}
+
+	#[inline]
+	fn size(&self) -> u64 {
+		self.size
+	}
+
+	#[inline]
+	fn ptr(&self) -> Option<*mut c_void> {
+		match &self.block.memory {
+			BlockMemory::Mapped(mapped) => mapped.ptr_at(self.offset),
+			BlockMemory::Unmapped(_) => None
+		}
+	}
+}
+
+impl Slot {
+	/// Returns the persistent mapping of the block in which the slot is allocated,
+	/// if it is host visible.
+	#[inline]
+	pub fn mapped_memory(&self) -> Option<&device::SharedMappedMemory> {
+		match &self.block.memory {
+			BlockMemory::Mapped(mapped) => Some(mapped),
+			BlockMemory::Unmapped(_) => None
+		}
+	}
+}
+
+impl Drop for Slot {
+	fn drop(&mut self) {
+		let empty = {
+			let mut state = self.block.state.lock();
+			state.free(self.offset);
+			state.is_empty()
+		};
+
+		if empty {
+			if let Some(pool) = self.block.pool.upgrade() {
+				pool.lock().release_if_empty(&self.block)
+			}
+		}
+	}
 }
 
 /// Allocator without logical memory limit.
 ///
 /// The allocator is still limited by the actual physical memory size.
+/// Memory is allocated from the device by blocks of (at least) `block_size` bytes,
+/// from which slots are then allocated according to the allocator `Strategy`.
+/// Host visible blocks are persistently mapped.
+/// Empty blocks are released back to the device,
+/// except for the last block of each memory type.
+///
+/// Resources that prefer or require a dedicated allocation
+/// (see `MemoryRequirements::prefers_dedicated`) get their own block.
 pub struct Unbounded {
-	// ...
+	device: Arc<Device>,
+	strategy: Strategy,
+	block_size: u64,
+	granularity: u64,
+	non_coherent_atom_size: u64,
+	pools: Vec<Arc<Mutex<Pool>>>,
+	hook: Mutex<Option<Arc<dyn MemoryHook>>>
+}
+
+impl Unbounded {
+	/// Creates a new allocator using blocks of `DEFAULT_BLOCK_SIZE` bytes.
+	pub fn new(device: &Arc<Device>, strategy: Strategy) -> Self {
+		Self::with_block_size(device, strategy, DEFAULT_BLOCK_SIZE)
+	}
+
+	/// Creates a new allocator using blocks of the given size (in bytes).
+	pub fn with_block_size(device: &Arc<Device>, strategy: Strategy, block_size: u64) -> Self {
+		let physical_device = device.physical_device();
+		let pools = (0..physical_device.memory_type_count()).map(|_| Arc::new(Mutex::new(Pool::default()))).collect();
+
+		Self {
+			device: device.clone(),
+			strategy,
+			block_size,
+			granularity: physical_device.limits().buffer_image_granularity(),
+			non_coherent_atom_size: physical_device.limits().non_coherent_atom_size(),
+			pools,
+			hook: Mutex::new(None)
+		}
+	}
+
+	#[inline]
+	pub fn strategy(&self) -> Strategy {
+		self.strategy
+	}
+
+	#[inline]
+	pub fn block_size(&self) -> u64 {
+		self.block_size
+	}
+
+	/// Plans the defragmentation of the allocator blocks.
+	///
+	/// The given buffers and images (with their current layout) are candidates for relocation.
+	/// Blocks are evacuated from the least to the most used,
+	/// by relocating their resources in more used blocks of the same memory type.
+	/// A block is only evacuated if all its slots are candidates and fit in the other blocks.
+	/// Dedicated blocks are never evacuated nor used as destination.
+	///
+	/// Only the resources created with both the transfer source and transfer destination usages
+	/// can be relocated; the other ones are ignored, and their blocks are not evacuated.
+	///
+	/// See `Defragmentation` to perform the planned relocations.
+	/// Views and descriptors of the relocated resources must be recreated afterward.
+	pub fn defragment<'a, B, I>(&self, buffers: B, images: I) -> Result<Defragmentation<'a>, defrag::Error>
+	where
+		B: IntoIterator<Item=&'a mut buffer::Bound<Slot>>,
+		I: IntoIterator<Item=(&'a mut image::Bound<Slot>, image::Layout)>
+	{
+		use defrag::Candidate;
+		let mut candidates: Vec<Option<Candidate<'a>>> = buffers.into_iter().map(Candidate::Buffer)
+			.chain(images.into_iter().map(|(i, layout)| Candidate::Image(i, layout)))
+			.filter(Candidate::is_relocatable)
+			.map(Some)
+			.collect();
+
+		let mut moves = Vec::new();
+
+		for pool in &self.pools {
+			let blocks: Vec<Arc<Block>> = pool.lock().blocks.iter().filter(|b| !b.dedicated).cloned().collect();
+
+			let mut used: Vec<(Arc<Block>, u64)> = blocks.into_iter().map(|block| {
+				let (slots, _) = block.state.lock().dump();
+				let bytes = slots.iter().map(|s| s.size).sum();
+				(block, bytes)
+			}).collect();
+			used.sort_by_key(|(_, bytes)| *bytes);
+
+			let mut evacuated: Vec<Arc<Block>> = Vec::new();
+			for (i, (block, _)) in used.iter().enumerate() {
+				let slot_count = block.state.lock().dump().0.len();
+				let indexes: Vec<usize> = candidates.iter().enumerate().filter_map(|(c, candidate)| {
+					candidate.as_ref().filter(|candidate| Arc::ptr_eq(&candidate.slot().block, block)).map(|_| c)
+				}).collect();
+
+				if slot_count == 0 || indexes.len() != slot_count {
+					continue
+				}
+
+				// destination blocks, from the most used.
+				let targets: Vec<&Arc<Block>> = used[(i + 1)..].iter().rev()
+					.map(|(b, _)| b)
+					.filter(|b| !evacuated.iter().any(|e| Arc::ptr_eq(e, b)))
+					.collect();
+
+				let mut block_moves = Vec::new();
+				let mut complete = true;
+				for c in indexes.iter().copied() {
+					let mut candidate = candidates[c].take();
+					for target in &targets {
+						match candidate.take().unwrap().relocate(target)? {
+							Ok(m) => {
+								block_moves.push((c, m));
+								break
+							},
+							Err(back) => candidate = Some(back)
+						}
+					}
+
+					if candidate.is_some() {
+						candidates[c] = candidate;
+						complete = false;
+						break
+					}
+				}
+
+				if complete {
+					evacuated.push(block.clone());
+					moves.extend(block_moves.into_iter().map(|(_, m)| m))
+				} else {
+					// cancel the moves, releasing the allocated slots.
+					for (c, m) in block_moves {
+						candidates[c] = Some(m.cancel())
+					}
+				}
+			}
+		}
+
+		Ok(Defragmentation::new(&self.device, moves))
+	}
+
+	/// Aligns the offset and size of slots in host-visible, non-coherent memory types
+	/// to the `nonCoherentAtomSize` limit of the device.
+	///
+	/// This way, flushing or invalidating a slot never affects its neighbours.
+	fn atom_aligned(&self, memory_type: &MemoryType, memory_requirements: &MemoryRequirements) -> MemoryRequirements {
+		if memory_type.is_host_visible() && !memory_type.is_host_coherent() {
+			let atom_size = self.non_coherent_atom_size;
+			memory_requirements.align_to(atom_size).resized(align_up(memory_requirements.size(), atom_size))
+		} else {
+			*memory_requirements
+		}
+	}
+
+	/// Allocates memory in the most appropriate memory type for the given requirements.
+	///
+	/// If the allocation fails, the next appropriate memory type is tried
+	/// (see `MemoryRequirements::memory_types`).
+	fn allocate_ranked(&self, memory_requirements: &MemoryRequirements) -> Result<Slot, Error> {
+		let mut result = Err(Error::NoSuitableMemoryType);
+
+		for memory_type in memory_requirements.memory_types(self.device.physical_device()) {
+			result = self.allocate_in(memory_type, memory_requirements);
+			if result.is_ok() {
+				break
+			}
+		}
+
+		result
+	}
+
+	fn allocate_in(&self, memory_type: MemoryType, memory_requirements: &MemoryRequirements) -> Result<Slot, Error> {
+		let pool_ref = &self.pools[memory_type.index() as usize];
+		let mut pool = pool_ref.lock();
+
+		if memory_requirements.prefers_dedicated() || memory_requirements.requires_dedicated() {
+			let block = Arc::new(Block::dedicated(&self.device, memory_type, memory_requirements, Arc::downgrade(pool_ref), self.hook.lock().clone())?);
+			let offset = block.state.lock().allocate(memory_requirements).unwrap();
+			pool.blocks.push(block.clone());
+
+			return Ok(Slot {
+				block,
+				offset,
+				size: memory_requirements.size()
+			})
+		}
+
+		let memory_requirements = &self.atom_aligned(&memory_type, memory_requirements);
+		pool.prepared = pool.prepared.saturating_sub(align_up(memory_requirements.size(), memory_requirements.alignment()));
+
+		for block in pool.blocks.iter().filter(|b| !b.dedicated) {
+			if let Some(offset) = block.state.lock().allocate(memory_requirements) {
+				return Ok(Slot {
+					block: block.clone(),
+					offset,
+					size: memory_requirements.size()
+				})
+			}
+		}
+
+		let block_size = std::cmp::max(
+			std::cmp::min(std::cmp::max(self.block_size, pool.prepared + memory_requirements.size()), memory_type.size()),
+			memory_requirements.size()
+		);
+
+		let mut state = BlockState::new(self.strategy, block_size, self.granularity);
+		let offset = state.allocate(memory_requirements).unwrap();
+		let block = Arc::new(Block::new(&self.device, memory_type, block_size, state, Arc::downgrade(pool_ref), self.hook.lock().clone())?);
+		pool.blocks.push(block.clone());
+
+		Ok(Slot {
+			block,
+			offset,
+			size: memory_requirements.size()
+		})
+	}
+}
+
+unsafe impl super::Allocator for Unbounded {
+	type Slot = Slot;
+
+	fn prepare(&self, memory_requirements: MemoryRequirements) {
+		if memory_requirements.prefers_dedicated() || memory_requirements.requires_dedicated() {
+			return
+		}
+
+		if let Some(memory_type) = memory_requirements.memory_types(self.device.physical_device()).into_iter().next() {
+			let memory_requirements = self.atom_aligned(&memory_type, &memory_requirements);
+			let mut pool = self.pools[memory_type.index() as usize].lock();
+			pool.prepared += align_up(memory_requirements.size(), memory_requirements.alignment())
+		}
+	}
+
+	fn allocate(&self, memory_requirements: MemoryRequirements) -> Result<Slot, Error> {
+		self.allocate_ranked(&memory_requirements)
+	}
+
+	fn reallocate(&self, slot: HostVisible<Slot>, memory_requirements: MemoryRequirements) -> Result<HostVisible<Slot>, Error> {
+		let (mut slot, dirty) = slot.into_raw_parts();
+
+		let memory_type = slot.block.memory.memory().memory_type();
+		let resized = self.atom_aligned(&memory_type, &memory_requirements);
+		if memory_requirements.contains_memory_type_index(memory_type.index())
+		&& slot.block.state.lock().resize(slot.offset, &resized) {
+			slot.size = resized.size();
+			return Ok(HostVisible::from_raw_parts(slot, dirty))
+		}
+
+		let memory_requirements = memory_requirements.host_visible(self.device.physical_device());
+		let new_slot = self.allocate_ranked(&memory_requirements)?;
+
+		use super::Slot as _;
+		let len = std::cmp::min(slot.size, new_slot.size);
+		unsafe {
+			std::ptr::copy_nonoverlapping(
+				slot.ptr().unwrap() as *const u8,
+				new_slot.ptr().unwrap() as *mut u8,
+				len as usize
+			)
+		}
+
+		let new_slot = HostVisible::try_from(new_slot).ok().unwrap();
+		new_slot.mark_dirty(0..len);
+		Ok(new_slot)
+	}
+
+	fn set_memory_hook(&self, hook: Arc<dyn MemoryHook>) -> bool {
+		*self.hook.lock() = Some(hook);
+		true
+	}
+}
+
+impl stats::Inspect for Unbounded {
+	fn dump(&self) -> stats::Dump {
+		let mut blocks = Vec::new();
+
+		for pool in &self.pools {
+			let pool = pool.lock();
+			for block in &pool.blocks {
+				let memory = block.memory.memory();
+				let (slots, free_ranges) = block.state.lock().dump();
+				blocks.push(stats::BlockDump {
+					memory_type: memory.memory_type().index(),
+					heap: memory.memory_type().heap_index(),
+					size: memory.size(),
+					mapped: block.memory.ptr().is_some(),
+					dedicated: block.dedicated,
+					slots,
+					free_ranges
+				})
+			}
+		}
+
+		stats::Dump {
+			blocks
+		}
+	}
+}
+
+impl DeviceOwned for Unbounded {
+	fn device(&self) -> &Arc<Device> {
+		&self.device
+	}
 }
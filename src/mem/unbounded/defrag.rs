@@ -0,0 +1,359 @@
+use std::{
+	sync::Arc,
+	fmt
+};
+use ash::{
+	vk,
+	version::DeviceV1_0
+};
+use crate::{
+	OomError,
+	Device,
+	DeviceOwned,
+	command,
+	image,
+	mem::buffer,
+	resource::Reference,
+	sync::{
+		fence,
+		future::SignalFence
+	}
+};
+use super::{
+	Block,
+	Slot
+};
+
+#[derive(Debug)]
+pub enum Error {
+	BufferCreation(buffer::CreationError),
+	ImageCreation(OomError),
+	BufferBind(buffer::BindError),
+	ImageBind(image::BindError)
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::BufferCreation(e) => write!(f, "buffer creation failed: {}", e),
+			Error::ImageCreation(e) => write!(f, "image creation failed: {}", e),
+			Error::BufferBind(e) => write!(f, "buffer bind failed: {}", e),
+			Error::ImageBind(e) => write!(f, "image bind failed: {}", e)
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+		match self {
+			Error::BufferCreation(e) => Some(e),
+			Error::ImageCreation(e) => Some(e),
+			Error::BufferBind(e) => Some(e),
+			Error::ImageBind(e) => Some(e)
+		}
+	}
+}
+
+impl From<buffer::CreationError> for Error {
+	fn from(e: buffer::CreationError) -> Self {
+		Self::BufferCreation(e)
+	}
+}
+
+impl From<buffer::BindError> for Error {
+	fn from(e: buffer::BindError) -> Self {
+		Self::BufferBind(e)
+	}
+}
+
+impl From<image::BindError> for Error {
+	fn from(e: image::BindError) -> Self {
+		Self::ImageBind(e)
+	}
+}
+
+/// Resource that may be relocated by a defragmentation.
+pub(super) enum Candidate<'a> {
+	Buffer(&'a mut buffer::Bound<Slot>),
+	Image(&'a mut image::Bound<Slot>, image::Layout)
+}
+
+impl<'a> Candidate<'a> {
+	#[inline]
+	pub(super) fn slot(&self) -> &Slot {
+		match self {
+			Candidate::Buffer(b) => b.memory_slot(),
+			Candidate::Image(i, _) => i.memory_slot()
+		}
+	}
+
+	/// Checks if the resource can be copied into a duplicate of itself.
+	///
+	/// This requires both the transfer source and destination usages,
+	/// since the duplicate is created with the same usages.
+	pub(super) fn is_relocatable(&self) -> bool {
+		match self {
+			Candidate::Buffer(b) => {
+				let usage = b.unbound().usage();
+				usage.transfer_source() && usage.transfer_destination()
+			},
+			Candidate::Image(i, _) => {
+				let usage = i.unbound().usage();
+				usage.transfer_source && usage.transfer_destination
+			}
+		}
+	}
+
+	/// Creates a copy of the resource in the given block, if there is enough room.
+	///
+	/// Returns `Ok(Err(self))` if the block cannot hold the resource.
+	pub(super) fn relocate(self, block: &Arc<Block>) -> Result<Result<Move<'a>, Self>, Error> {
+		match self {
+			Candidate::Buffer(resource) => {
+				let new = resource.unbound().duplicate()?;
+				match allocate_in(block, &new.memory_requirements()) {
+					Some(slot) => {
+						let new = unsafe { new.bind(slot).map_err(|(_, e)| e)? };
+						Ok(Ok(Move::Buffer { resource, new }))
+					},
+					None => Ok(Err(Candidate::Buffer(resource)))
+				}
+			},
+			Candidate::Image(resource, layout) => {
+				let new = resource.unbound().duplicate().map_err(Error::ImageCreation)?;
+				match allocate_in(block, &new.memory_requirements()) {
+					Some(slot) => {
+						let new = unsafe { new.bind(slot).map_err(|(_, e)| e)? };
+						Ok(Ok(Move::Image { resource, layout, new }))
+					},
+					None => Ok(Err(Candidate::Image(resource, layout)))
+				}
+			}
+		}
+	}
+}
+
+fn allocate_in(block: &Arc<Block>, memory_requirements: &crate::mem::MemoryRequirements) -> Option<Slot> {
+	if !memory_requirements.contains_memory_type_index(block.memory.memory().memory_type().index()) {
+		return None
+	}
+
+	let offset = block.state.lock().allocate(memory_requirements)?;
+	Some(Slot {
+		block: block.clone(),
+		offset,
+		size: memory_requirements.size()
+	})
+}
+
+/// Planned resource relocation.
+pub(super) enum Move<'a> {
+	Buffer {
+		resource: &'a mut buffer::Bound<Slot>,
+		new: buffer::Bound<Slot>
+	},
+	Image {
+		resource: &'a mut image::Bound<Slot>,
+		layout: image::Layout,
+		new: image::Bound<Slot>
+	}
+}
+
+impl<'a> Move<'a> {
+	/// Cancels the move, releasing the slot allocated for the copy.
+	pub(super) fn cancel(self) -> Candidate<'a> {
+		match self {
+			Move::Buffer { resource, .. } => Candidate::Buffer(resource),
+			Move::Image { resource, layout, .. } => Candidate::Image(resource, layout)
+		}
+	}
+
+	fn size(&self) -> u64 {
+		use crate::mem::Slot as _;
+		match self {
+			Move::Buffer { new, .. } => new.memory_slot().size(),
+			Move::Image { new, .. } => new.memory_slot().size()
+		}
+	}
+}
+
+/// Defragmentation pass of an `Unbounded` allocator.
+///
+/// Created by `Unbounded::defragment`, it holds a copy of each relocated resource,
+/// allocated in a denser block.
+/// The copies must be recorded into a command buffer with `record`.
+/// Once the submission of this command buffer is done,
+/// `finish` rebinds the relocated resources to their new memory slot,
+/// releasing their old slot and the emptied blocks.
+///
+/// Relocated resources are replaced by new Vulkan objects:
+/// image views, buffer views, descriptor sets and command buffers
+/// referring to them are invalidated by `finish` and must be recreated.
+///
+/// Dropping the defragmentation before calling `finish` cancels it.
+#[must_use]
+pub struct Defragmentation<'a> {
+	device: Arc<Device>,
+	moves: Vec<Move<'a>>
+}
+
+impl<'a> Defragmentation<'a> {
+	pub(super) fn new(device: &Arc<Device>, moves: Vec<Move<'a>>) -> Self {
+		Self {
+			device: device.clone(),
+			moves
+		}
+	}
+
+	/// Checks if there is nothing to relocate.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.moves.is_empty()
+	}
+
+	/// Number of relocated resources.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.moves.len()
+	}
+
+	/// Number of bytes copied by the relocation.
+	pub fn moved_bytes(&self) -> u64 {
+		self.moves.iter().map(Move::size).sum()
+	}
+
+	/// Records the copy of every relocated resource.
+	///
+	/// The resources must not be in use by the device when the command buffer is executed,
+	/// and images must be in the layout given to `Unbounded::defragment`.
+	/// Relocated images are left in the same layout.
+	pub fn record<B: command::Buffer>(&self, recorder: &mut command::buffer::Recorder<B>) {
+		let device = self.device.handle();
+		let cb = recorder.buffer.handle();
+
+		let mut before = Vec::new();
+		let mut after = Vec::new();
+		for m in &self.moves {
+			if let Move::Image { resource, layout, new } = m {
+				let range = subresource_range(resource.unbound());
+				before.push(image_barrier(new.handle(), range, vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE));
+				before.push(image_barrier(resource.handle(), range, layout.into_vulkan(), vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::AccessFlags::MEMORY_WRITE, vk::AccessFlags::TRANSFER_READ));
+				after.push(image_barrier(new.handle(), range, vk::ImageLayout::TRANSFER_DST_OPTIMAL, layout.into_vulkan(), vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE));
+			}
+		}
+
+		let memory_before = vk::MemoryBarrier {
+			src_access_mask: vk::AccessFlags::MEMORY_WRITE,
+			dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+			..Default::default()
+		};
+
+		let memory_after = vk::MemoryBarrier {
+			src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+			dst_access_mask: vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
+			..Default::default()
+		};
+
+		unsafe {
+			device.cmd_pipeline_barrier(cb, vk::PipelineStageFlags::ALL_COMMANDS, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[memory_before], &[], &before);
+
+			for m in &self.moves {
+				match m {
+					Move::Buffer { resource, new } => {
+						let region = vk::BufferCopy {
+							src_offset: 0,
+							dst_offset: 0,
+							size: resource.unbound().len()
+						};
+
+						device.cmd_copy_buffer(cb, resource.handle(), new.handle(), &[region])
+					},
+					Move::Image { resource, new, .. } => {
+						let regions = copy_regions(resource.unbound());
+						device.cmd_copy_image(cb, resource.handle(), vk::ImageLayout::TRANSFER_SRC_OPTIMAL, new.handle(), vk::ImageLayout::TRANSFER_DST_OPTIMAL, &regions)
+					}
+				}
+			}
+
+			device.cmd_pipeline_barrier(cb, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[memory_after], &[], &after);
+		}
+	}
+
+	/// Waits for the given future, signaled after the recorded copies, and rebinds the relocated resources.
+	///
+	/// The old slots of the resources are released,
+	/// and the blocks emptied by the relocation are freed.
+	pub fn finish<F: SignalFence>(self, future: F) -> Result<(), fence::WaitError> {
+		future.wait(None)?;
+
+		for m in self.moves {
+			match m {
+				Move::Buffer { resource, new } => {
+					*resource = new
+				},
+				Move::Image { resource, new, .. } => {
+					*resource = new
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl<'a> DeviceOwned for Defragmentation<'a> {
+	fn device(&self) -> &Arc<Device> {
+		&self.device
+	}
+}
+
+fn subresource_range(image: &image::Unbound) -> vk::ImageSubresourceRange {
+	vk::ImageSubresourceRange {
+		aspect_mask: image::view::Aspects::of_format(image.format()).into_vulkan(),
+		base_mip_level: 0,
+		level_count: image.mip_levels(),
+		base_array_layer: 0,
+		layer_count: image.array_layers()
+	}
+}
+
+fn image_barrier(image: vk::Image, range: vk::ImageSubresourceRange, old_layout: vk::ImageLayout, new_layout: vk::ImageLayout, src_access_mask: vk::AccessFlags, dst_access_mask: vk::AccessFlags) -> vk::ImageMemoryBarrier {
+	vk::ImageMemoryBarrier {
+		src_access_mask,
+		dst_access_mask,
+		old_layout,
+		new_layout,
+		src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+		dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+		image,
+		subresource_range: range,
+		..Default::default()
+	}
+}
+
+/// Regions covering every mip level and array layer of the image.
+fn copy_regions(image: &image::Unbound) -> Vec<vk::ImageCopy> {
+	let aspect_mask = image::view::Aspects::of_format(image.format()).into_vulkan();
+	let (width, height, depth) = image.size();
+
+	(0..image.mip_levels()).map(|level| {
+		let subresource = vk::ImageSubresourceLayers {
+			aspect_mask,
+			mip_level: level,
+			base_array_layer: 0,
+			layer_count: image.array_layers()
+		};
+
+		vk::ImageCopy {
+			src_subresource: subresource,
+			src_offset: vk::Offset3D::default(),
+			dst_subresource: subresource,
+			dst_offset: vk::Offset3D::default(),
+			extent: vk::Extent3D {
+				width: std::cmp::max(width >> level, 1),
+				height: std::cmp::max(height >> level, 1),
+				depth: std::cmp::max(depth >> level, 1)
+			}
+		}
+	}).collect()
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use crate::mem::stats::{
+	SlotDump,
+	FreeRange
+};
+use super::{
+	align_up,
+	on_same_page
+};
+
+/// Linear allocation state of a block.
+///
+/// Slots are allocated one after the other.
+/// The space of the block is only reclaimed once every slot allocated in it has been released.
+pub struct Linear {
+	/// Size of the block.
+	size: u64,
+
+	/// Buffer-image granularity.
+	granularity: u64,
+
+	/// Offset of the first free byte in the block.
+	cursor: u64,
+
+	/// Slots currently allocated in the block, with their size and linearity.
+	slots: BTreeMap<u64, (u64, bool)>,
+
+	/// Offset and linearity of the last slot allocated in the block, if any.
+	last: Option<(u64, bool)>
+}
+
+impl Linear {
+	pub fn new(size: u64, granularity: u64) -> Self {
+		Self {
+			size,
+			granularity,
+			cursor: 0,
+			slots: BTreeMap::new(),
+			last: None
+		}
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.slots.is_empty()
+	}
+
+	pub fn allocate(&mut self, size: u64, alignment: u64, linear: bool) -> Option<u64> {
+		let mut offset = align_up(self.cursor, alignment);
+
+		if let Some((_, last_linear)) = self.last {
+			if last_linear != linear && on_same_page(self.cursor, offset, self.granularity) {
+				offset = align_up(offset, self.granularity)
+			}
+		}
+
+		if offset + size <= self.size {
+			self.cursor = offset + size;
+			self.slots.insert(offset, (size, linear));
+			self.last = Some((offset, linear));
+			Some(offset)
+		} else {
+			None
+		}
+	}
+
+	/// Resizes the slot at the given offset.
+	///
+	/// This only succeeds if the slot is the last allocated slot of the block.
+	pub fn resize(&mut self, offset: u64, size: u64, alignment: u64, linear: bool) -> bool {
+		if self.last == Some((offset, linear)) && offset % alignment == 0 && offset + size <= self.size {
+			self.cursor = offset + size;
+			self.slots.insert(offset, (size, linear));
+			true
+		} else {
+			false
+		}
+	}
+
+	pub fn free(&mut self, offset: u64) {
+		self.slots.remove(&offset);
+		if self.slots.is_empty() {
+			self.cursor = 0;
+			self.last = None;
+		}
+	}
+
+	pub fn dump(&self) -> (Vec<SlotDump>, Vec<FreeRange>) {
+		let slots = self.slots.iter().map(|(offset, (size, linear))| {
+			SlotDump {
+				offset: *offset,
+				size: *size,
+				linear: *linear
+			}
+		}).collect();
+
+		let mut free_ranges = Vec::new();
+		if self.cursor < self.size {
+			free_ranges.push(FreeRange {
+				offset: self.cursor,
+				size: self.size - self.cursor
+			})
+		}
+
+		(slots, free_ranges)
+	}
+}
@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use crate::mem::stats::{
+	SlotDump,
+	FreeRange
+};
+use super::{
+	align_up,
+	on_same_page
+};
+
+/// Log2 of the number of second level classes per first level class.
+const SL_LOG2: u32 = 5;
+
+/// Number of second level classes per first level class.
+const SL_COUNT: usize = 1 << SL_LOG2;
+
+/// Number of first level classes.
+const FL_COUNT: usize = 64;
+
+/// Contiguous range of a block.
+struct Range {
+	offset: u64,
+	size: u64,
+
+	/// Linearity of the resource allocated in this range, or `None` if the range is free.
+	linear: Option<bool>,
+
+	/// Previous physical range.
+	prev: Option<usize>,
+
+	/// Next physical range.
+	next: Option<usize>,
+
+	/// Previous free range in the same size class.
+	prev_free: Option<usize>,
+
+	/// Next free range in the same size class.
+	next_free: Option<usize>
+}
+
+impl Range {
+	#[inline]
+	fn end(&self) -> u64 {
+		self.offset + self.size
+	}
+
+	#[inline]
+	fn is_free(&self) -> bool {
+		self.linear.is_none()
+	}
+}
+
+/// Returns the first and second level classes of the given size.
+#[inline]
+fn mapping(size: u64) -> (usize, usize) {
+	let fl = 63 - size.leading_zeros();
+	let sl = if fl >= SL_LOG2 {
+		(size >> (fl - SL_LOG2)) as usize
+	} else {
+		(size << (SL_LOG2 - fl)) as usize
+	};
+
+	(fl as usize, sl - SL_COUNT)
+}
+
+/// Two-Level Segregated Fit allocation state of a block.
+///
+/// Free ranges are stored in segregated lists indexed by size classes,
+/// allowing constant time good-fit searches.
+/// Released ranges are coalesced with their free neighbors.
+pub struct Tlsf {
+	/// Buffer-image granularity.
+	granularity: u64,
+
+	/// Ranges storage.
+	///
+	/// The range at index 0 is always the first range of the block.
+	ranges: Vec<Range>,
+
+	/// Indexes of unused entries in `ranges`.
+	unused: Vec<usize>,
+
+	/// Bitmap of the first level classes having a non-empty second level class.
+	fl_bitmap: u64,
+
+	/// Bitmaps of non-empty second level classes, for each first level class.
+	sl_bitmaps: [u32; FL_COUNT],
+
+	/// Head of the free list of each class.
+	heads: Vec<Option<usize>>,
+
+	/// Allocated ranges, by offset.
+	allocated: HashMap<u64, usize>
+}
+
+impl Tlsf {
+	pub fn new(size: u64, granularity: u64) -> Self {
+		let mut tlsf = Self {
+			granularity,
+			ranges: Vec::new(),
+			unused: Vec::new(),
+			fl_bitmap: 0,
+			sl_bitmaps: [0; FL_COUNT],
+			heads: vec![None; FL_COUNT * SL_COUNT],
+			allocated: HashMap::new()
+		};
+
+		let r = tlsf.new_range(0, size, None, None);
+		tlsf.insert_free(r);
+		tlsf
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.allocated.is_empty()
+	}
+
+	fn new_range(&mut self, offset: u64, size: u64, prev: Option<usize>, next: Option<usize>) -> usize {
+		let range = Range {
+			offset,
+			size,
+			linear: None,
+			prev,
+			next,
+			prev_free: None,
+			next_free: None
+		};
+
+		match self.unused.pop() {
+			Some(r) => {
+				self.ranges[r] = range;
+				r
+			},
+			None => {
+				self.ranges.push(range);
+				self.ranges.len() - 1
+			}
+		}
+	}
+
+	fn insert_free(&mut self, r: usize) {
+		let (fl, sl) = mapping(self.ranges[r].size);
+		let head = self.heads[fl * SL_COUNT + sl];
+
+		self.ranges[r].linear = None;
+		self.ranges[r].prev_free = None;
+		self.ranges[r].next_free = head;
+		if let Some(head) = head {
+			self.ranges[head].prev_free = Some(r)
+		}
+
+		self.heads[fl * SL_COUNT + sl] = Some(r);
+		self.sl_bitmaps[fl] |= 1 << sl;
+		self.fl_bitmap |= 1 << fl;
+	}
+
+	fn remove_free(&mut self, r: usize) {
+		let (fl, sl) = mapping(self.ranges[r].size);
+		let prev_free = self.ranges[r].prev_free.take();
+		let next_free = self.ranges[r].next_free.take();
+
+		match prev_free {
+			Some(p) => self.ranges[p].next_free = next_free,
+			None => self.heads[fl * SL_COUNT + sl] = next_free
+		}
+
+		if let Some(n) = next_free {
+			self.ranges[n].prev_free = prev_free
+		}
+
+		if self.heads[fl * SL_COUNT + sl].is_none() {
+			self.sl_bitmaps[fl] &= !(1 << sl);
+			if self.sl_bitmaps[fl] == 0 {
+				self.fl_bitmap &= !(1 << fl)
+			}
+		}
+	}
+
+	/// Removes the range `r` from the physical list and marks it unused.
+	fn unlink(&mut self, r: usize) {
+		let prev = self.ranges[r].prev;
+		let next = self.ranges[r].next;
+
+		if let Some(p) = prev {
+			self.ranges[p].next = next
+		}
+
+		if let Some(n) = next {
+			self.ranges[n].prev = prev
+		}
+
+		self.unused.push(r)
+	}
+
+	/// Splits the range `r` at the given offset, returning the new range starting at `offset`.
+	fn split(&mut self, r: usize, offset: u64) -> usize {
+		let end = self.ranges[r].end();
+		let next = self.ranges[r].next;
+		let new = self.new_range(offset, end - offset, Some(r), next);
+
+		if let Some(n) = next {
+			self.ranges[n].prev = Some(new)
+		}
+
+		self.ranges[r].next = Some(new);
+		self.ranges[r].size = offset - self.ranges[r].offset;
+		new
+	}
+
+	/// Checks if the next allocated range after `end` conflicts with a resource of the given linearity.
+	fn conflicts_with_next(&self, next: Option<usize>, end: u64, linear: bool) -> bool {
+		match next {
+			Some(n) => {
+				let n = &self.ranges[n];
+				n.linear.map(|l| l != linear).unwrap_or(false) && on_same_page(end, n.offset, self.granularity)
+			},
+			None => false
+		}
+	}
+
+	/// Returns the offset at which a resource can be placed in the free range `r`, if any.
+	fn fit(&self, r: usize, size: u64, alignment: u64, linear: bool) -> Option<u64> {
+		let range = &self.ranges[r];
+		let mut offset = align_up(range.offset, alignment);
+
+		if let Some(p) = range.prev {
+			let p = &self.ranges[p];
+			if p.linear.map(|l| l != linear).unwrap_or(false) && on_same_page(p.end(), offset, self.granularity) {
+				offset = align_up(offset, self.granularity)
+			}
+		}
+
+		if offset + size <= range.end() && !self.conflicts_with_next(range.next, offset + size, linear) {
+			Some(offset)
+		} else {
+			None
+		}
+	}
+
+	/// Finds a free range able to hold the given resource.
+	fn find(&self, size: u64, alignment: u64, linear: bool) -> Option<(usize, u64)> {
+		let (mut fl, sl) = mapping(size);
+		let mut sl_map = self.sl_bitmaps[fl] & (!0u32 << sl);
+
+		loop {
+			while sl_map != 0 {
+				let sl = sl_map.trailing_zeros() as usize;
+				let mut current = self.heads[fl * SL_COUNT + sl];
+				while let Some(r) = current {
+					if let Some(offset) = self.fit(r, size, alignment, linear) {
+						return Some((r, offset))
+					}
+
+					current = self.ranges[r].next_free
+				}
+
+				sl_map &= !(1 << sl)
+			}
+
+			let fl_map = if fl + 1 < FL_COUNT {
+				self.fl_bitmap & (!0u64 << (fl + 1))
+			} else {
+				0
+			};
+
+			if fl_map == 0 {
+				return None
+			}
+
+			fl = fl_map.trailing_zeros() as usize;
+			sl_map = self.sl_bitmaps[fl]
+		}
+	}
+
+	pub fn allocate(&mut self, size: u64, alignment: u64, linear: bool) -> Option<u64> {
+		let size = std::cmp::max(size, 1);
+		let (mut r, offset) = self.find(size, alignment, linear)?;
+		self.remove_free(r);
+
+		if offset > self.ranges[r].offset {
+			let front = r;
+			r = self.split(front, offset);
+			self.insert_free(front)
+		}
+
+		if self.ranges[r].size > size {
+			let back = self.split(r, offset + size);
+			self.insert_free(back)
+		}
+
+		self.ranges[r].linear = Some(linear);
+		self.allocated.insert(offset, r);
+		Some(offset)
+	}
+
+	/// Resizes the slot at the given offset.
+	///
+	/// This only succeeds if the slot can be grown (or shrunk) in place.
+	pub fn resize(&mut self, offset: u64, size: u64, alignment: u64, linear: bool) -> bool {
+		let size = std::cmp::max(size, 1);
+		let r = match self.allocated.get(&offset) {
+			Some(r) => *r,
+			None => return false
+		};
+
+		if offset % alignment != 0 || self.ranges[r].linear != Some(linear) {
+			return false
+		}
+
+		let current_size = self.ranges[r].size;
+		if size <= current_size {
+			if size < current_size {
+				let back = self.split(r, offset + size);
+				self.coalesce_and_insert(back);
+			}
+
+			return true
+		}
+
+		match self.ranges[r].next {
+			Some(n) if self.ranges[n].is_free() && offset + size <= self.ranges[n].end() => {
+				if self.conflicts_with_next(self.ranges[n].next, offset + size, linear) {
+					return false
+				}
+
+				self.remove_free(n);
+				let n_end = self.ranges[n].end();
+				if offset + size < n_end {
+					let back = self.split(n, offset + size);
+					self.insert_free(back)
+				}
+
+				self.ranges[r].size += self.ranges[n].size;
+				self.unlink(n);
+				true
+			},
+			_ => false
+		}
+	}
+
+	pub fn free(&mut self, offset: u64) {
+		let r = self.allocated.remove(&offset).expect("no slot allocated at this offset");
+		self.coalesce_and_insert(r)
+	}
+
+	/// Merges the range `r` with its free neighbors and inserts the result in the free lists.
+	fn coalesce_and_insert(&mut self, mut r: usize) {
+		if let Some(n) = self.ranges[r].next {
+			if self.ranges[n].is_free() {
+				self.remove_free(n);
+				self.ranges[r].size += self.ranges[n].size;
+				self.unlink(n)
+			}
+		}
+
+		if let Some(p) = self.ranges[r].prev {
+			if self.ranges[p].is_free() {
+				self.remove_free(p);
+				self.ranges[p].size += self.ranges[r].size;
+				self.unlink(r);
+				r = p
+			}
+		}
+
+		self.insert_free(r)
+	}
+
+	pub fn dump(&self) -> (Vec<SlotDump>, Vec<FreeRange>) {
+		let mut slots = Vec::new();
+		let mut free_ranges = Vec::new();
+
+		let mut current = Some(0);
+		while let Some(r) = current {
+			let range = &self.ranges[r];
+			match range.linear {
+				Some(linear) => slots.push(SlotDump {
+					offset: range.offset,
+					size: range.size,
+					linear
+				}),
+				None => free_ranges.push(FreeRange {
+					offset: range.offset,
+					size: range.size
+				})
+			}
+
+			current = range.next
+		}
+
+		(slots, free_ranges)
+	}
+}
@@ -0,0 +1,142 @@
+use ash::vk;
+use crate::instance::{
+	PhysicalDevice,
+	physical_device::MemoryType
+};
+
+type Flags = vk::MemoryPropertyFlags;
+
+/// No required flag.
+const ANY: Flags = Flags::from_raw(0);
+
+/// Intended usage of some memory.
+///
+/// The usage is used by allocators to select the most appropriate memory type
+/// among the ones allowed by the memory requirements
+/// (see `MemoryRequirements::with_usage`).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MemoryUsage {
+	/// Memory only accessed by the device.
+	///
+	/// Device local memory is preferred.
+	GpuOnly,
+
+	/// Memory written by the host and read by the device, typically once.
+	///
+	/// Host visible memory is required.
+	/// On devices with a unified memory architecture, device local memory is preferred.
+	Upload,
+
+	/// Memory written by the device and read back by the host.
+	///
+	/// Host visible memory is required, and host cached memory is preferred.
+	Readback,
+
+	/// Memory frequently written by the host and read by the device.
+	///
+	/// Host visible memory is required, and device local memory is preferred.
+	Dynamic,
+
+	/// Device memory whose content does not outlive a render pass,
+	/// such as transient attachments.
+	///
+	/// Lazily allocated memory is preferred.
+	Transient
+}
+
+/// Memory type selection criteria.
+struct Criteria {
+	/// Ranked list of required flags.
+	///
+	/// Memory types satisfying an entry come before the ones only satisfying the next entries.
+	required: &'static [Flags],
+
+	/// Flags that should be present.
+	preferred: Flags,
+
+	/// Flags that should be absent.
+	avoided: Flags
+}
+
+impl MemoryUsage {
+	fn criteria(&self, uma: bool) -> Criteria {
+		match self {
+			MemoryUsage::GpuOnly => Criteria {
+				required: &[Flags::DEVICE_LOCAL, ANY],
+				preferred: Flags::empty(),
+				avoided: Flags::HOST_VISIBLE
+			},
+			MemoryUsage::Upload => Criteria {
+				required: &[Flags::HOST_VISIBLE],
+				preferred: if uma {
+					Flags::DEVICE_LOCAL | Flags::HOST_COHERENT
+				} else {
+					Flags::HOST_COHERENT
+				},
+				avoided: if uma {
+					Flags::HOST_CACHED
+				} else {
+					Flags::DEVICE_LOCAL | Flags::HOST_CACHED
+				}
+			},
+			MemoryUsage::Readback => Criteria {
+				required: &[Flags::HOST_VISIBLE],
+				preferred: Flags::HOST_CACHED | Flags::HOST_COHERENT,
+				avoided: Flags::empty()
+			},
+			MemoryUsage::Dynamic => Criteria {
+				required: &[Flags::HOST_VISIBLE],
+				preferred: Flags::DEVICE_LOCAL | Flags::HOST_COHERENT,
+				avoided: Flags::HOST_CACHED
+			},
+			MemoryUsage::Transient => Criteria {
+				required: &[Flags::DEVICE_LOCAL, ANY],
+				preferred: Flags::LAZILY_ALLOCATED,
+				avoided: Flags::HOST_VISIBLE
+			}
+		}
+	}
+
+	/// Returns the memory types suitable for this usage among the given memory type bits,
+	/// from the most to the least appropriate.
+	///
+	/// Memory types having the strongest required properties of the usage come first,
+	/// followed by the ones only having the next (weaker) requirements,
+	/// so that allocators can fall back to them when the first ones are exhausted.
+	/// Memory types that have none of the required properties are excluded.
+	/// The list is empty if no memory type is suitable.
+	pub fn memory_types<'a>(&self, physical_device: PhysicalDevice<'a>, memory_type_bits: u32) -> Vec<MemoryType<'a>> {
+		let criteria = self.criteria(physical_device.is_uma());
+		let mut memory_types = Vec::new();
+		let mut selected_bits = 0u32;
+
+		for required in criteria.required {
+			let mut tier: Vec<_> = physical_device.memory_types().filter(|memory_type| {
+				let bit = 1u32 << memory_type.index();
+				memory_type_bits & bit != 0 && selected_bits & bit == 0 && memory_type.property_flags().contains(*required)
+			}).collect();
+
+			// stable sort: memory types with the same score keep the order given by the device.
+			tier.sort_by_key(|memory_type| {
+				let flags = memory_type.property_flags();
+				let preferred = (flags & criteria.preferred).as_raw().count_ones() as i32;
+				let avoided = (flags & criteria.avoided).as_raw().count_ones() as i32;
+				avoided - preferred
+			});
+
+			for memory_type in &tier {
+				selected_bits |= 1u32 << memory_type.index();
+			}
+
+			memory_types.extend(tier)
+		}
+
+		memory_types
+	}
+
+	/// Returns the most appropriate memory type for this usage among the given memory type bits.
+	#[inline]
+	pub fn memory_type<'a>(&self, physical_device: PhysicalDevice<'a>, memory_type_bits: u32) -> Option<MemoryType<'a>> {
+		self.memory_types(physical_device, memory_type_bits).into_iter().next()
+	}
+}
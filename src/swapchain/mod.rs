@@ -297,6 +297,7 @@ unsafe impl<'a, W> task::Task for Acquire<'a, W> {
 	fn execute(
 		self,
 		signal_semaphore: Option<&[vk::Semaphore]>,
+		_signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<((u32, bool), Acquiring<W>), AcquireError> {
 		let ext_khr_swapchain = self.swapchain.inner.device.ext_khr_swapchain()?;
@@ -18,12 +18,21 @@ use super::{
 	Semaphore
 };
 
+mod waiter;
+mod pool;
+
+pub use waiter::*;
+pub use pool::*;
+
 pub type VulkanFence = vk::Fence;
 
 #[derive(Debug)]
 pub enum WaitError {
 	OutOfMemory(OomError),
-	DeviceLost
+	DeviceLost,
+
+	/// The fence was not signaled before the timeout.
+	Timeout
 }
 
 impl From<vk::Result> for WaitError {
@@ -32,6 +41,7 @@ impl From<vk::Result> for WaitError {
 			vk::Result::ERROR_OUT_OF_HOST_MEMORY => WaitError::OutOfMemory(OomError::Host),
 			vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => WaitError::OutOfMemory(OomError::Device),
 			vk::Result::ERROR_DEVICE_LOST => WaitError::DeviceLost,
+			vk::Result::TIMEOUT => WaitError::Timeout,
 			_ => unreachable!()
 		}
 	}
@@ -74,7 +84,7 @@ pub trait Fence: DeviceOwned {
 
 	/// Signal this fence after executing the given task.
 	fn signal<T: task::SignalFence>(self, task: T) -> Result<(T::Output, Future<T::Payload, Self>), T::Error> where Self: Sized {
-		let (output, payload) = task.execute(None, Some(*self.handle()))?;
+		let (output, payload) = task.execute(None, None, Some(*self.handle()))?;
 
 		let future = Future {
 			payload,
@@ -86,7 +96,8 @@ pub trait Fence: DeviceOwned {
 
 	/// Signal this fence after executing the given task.
 	fn signal_with_semaphore<S: Semaphore, T: task::SignalFence + task::SignalSemaphore>(self, semaphore: S, task: T) -> Result<(T::Output, FutureWithSemaphore<T::Payload, Self, S>), T::Error> where Self: Sized {
-		let (output, payload) = task.execute(Some(&[*semaphore.handle()]), Some(*self.handle()))?;
+		let (output, payload) = task.execute(Some(&[*semaphore.handle()]), None, Some(*self.handle()))?;
+		semaphore.signal_submitted();
 
 		let future = FutureWithSemaphore {
 			payload,
@@ -134,6 +145,19 @@ impl<P, F> Future<P, F> {
 	}
 }
 
+impl<P, F: Fence> Future<P, F> {
+	/// Block until the fence is signaled, and returns the payload of the future.
+	///
+	/// The future is given back if the fence is not signaled before the timeout (in nanoseconds).
+	pub fn wait_payload(self, timeout: Option<u64>) -> Result<Result<P, Self>, WaitError> {
+		match self.fence.wait(timeout) {
+			Ok(()) => Ok(Ok(self.payload)),
+			Err(WaitError::Timeout) => Ok(Err(self)),
+			Err(e) => Err(e)
+		}
+	}
+}
+
 unsafe impl<P: task::Payload, F: Fence> future::Future for Future<P, F> {
 	fn signal_fence(&self) -> Option<&VulkanFence> {
 		Some(self.fence.handle())
@@ -154,6 +178,18 @@ impl<P: task::Payload, F: Fence> future::SignalFence for Future<P, F> {
 	}
 }
 
+impl<P: task::Payload, F: Fence> future::WithPayload for Future<P, F> {
+	type Payload = P;
+
+	fn unwrap_payload(self) -> Result<P, UnwrapError<Self>> {
+		match self.fence.is_signaled() {
+			Ok(true) => Ok(self.payload),
+			Ok(false) => Err(UnwrapError::Unsignaled(self)),
+			Err(DeviceLost) => Err(UnwrapError::DeviceLost)
+		}
+	}
+}
+
 #[must_use]
 pub struct FutureWithSemaphore<P, F, S> {
 	payload: P,
@@ -176,6 +212,10 @@ unsafe impl<P: task::Payload, F: Fence, S: Semaphore> future::Future for FutureW
 		Some(self.fence.handle())
 	}
 
+	fn wait_submitted(&self) {
+		self.semaphore.wait_submitted()
+	}
+
 	// fn uses(&self, resource: &dyn resource::AbstractReference) -> bool {
 	// 	self.payload.uses(resource)
 	// }
@@ -192,6 +232,18 @@ impl<P: task::Payload, F: Fence, S: Semaphore> future::SignalFence for FutureWit
 	}
 }
 
+impl<P: task::Payload, F: Fence, S: Semaphore> future::WithPayload for FutureWithSemaphore<P, F, S> {
+	type Payload = P;
+
+	fn unwrap_payload(self) -> Result<P, UnwrapError<Self>> {
+		match self.fence.is_signaled() {
+			Ok(true) => Ok(self.payload),
+			Ok(false) => Err(UnwrapError::Unsignaled(self)),
+			Err(DeviceLost) => Err(UnwrapError::DeviceLost)
+		}
+	}
+}
+
 #[derive(Debug)]
 pub enum CreationError {
 	OutOfMemory(OomError)
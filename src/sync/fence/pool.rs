@@ -0,0 +1,83 @@
+use ash::version::DeviceV1_0;
+use std::{
+	borrow::Borrow,
+	sync::Arc
+};
+use crate::{
+	Device,
+	DeviceOwned,
+	sync::pool
+};
+use super::{
+	Raw,
+	CreationError
+};
+
+impl pool::Recycle for Raw {
+	/// Reset the fence if it is signaled.
+	///
+	/// An unsignaled fence may still be in use by a pending task, so it is destroyed instead.
+	fn recycle(&self) -> bool {
+		unsafe {
+			match self.device.handle().get_fence_status(self.handle) {
+				Ok(true) => self.device.handle().reset_fences(&[self.handle]).is_ok(),
+				_ => false
+			}
+		}
+	}
+}
+
+/// Fence pool.
+///
+/// Recycles the fences of the pool instead of destroying them.
+pub struct Pool {
+	device: Arc<Device>,
+	free: pool::Free<Raw>
+}
+
+impl Pool {
+	pub fn new(device: &Arc<Device>) -> Pool {
+		Pool {
+			device: device.clone(),
+			free: pool::Free::new()
+		}
+	}
+
+	/// Number of unused fences in the pool.
+	pub fn free_count(&self) -> usize {
+		self.free.len()
+	}
+
+	/// Get an unsignaled fence from the pool.
+	///
+	/// A new fence is created if the pool is empty.
+	pub fn get(&self) -> Result<Pooled, CreationError> {
+		Ok(Pooled(self.free.get(|| Raw::new(&self.device))?))
+	}
+}
+
+impl DeviceOwned for Pool {
+	fn device(&self) -> &Arc<Device> {
+		&self.device
+	}
+}
+
+/// Fence borrowed from a pool.
+///
+/// The fence is reset and returned to its pool when dropped,
+/// for instance when its future is waited or unwrapped.
+/// If it is not signaled at that point, it is destroyed instead.
+/// It must not be dropped while in use by a pending task.
+pub struct Pooled(pool::Borrowed<Raw>);
+
+impl Borrow<Raw> for Pooled {
+	fn borrow(&self) -> &Raw {
+		self.0.get()
+	}
+}
+
+impl DeviceOwned for Pooled {
+	fn device(&self) -> &Arc<Device> {
+		&self.0.get().device
+	}
+}
@@ -0,0 +1,260 @@
+use ash::{
+	vk,
+	version::DeviceV1_0
+};
+use std::{
+	collections::HashMap,
+	pin::Pin,
+	sync::{
+		Arc,
+		Mutex,
+		Condvar
+	},
+	task::{
+		Context,
+		Poll,
+		Waker
+	},
+	thread
+};
+use crate::Device;
+use super::{
+	future::WithPayload,
+	WaitError,
+	UnwrapError
+};
+
+/// Maximum time (in nanoseconds) the waiter thread blocks on the pending fences
+/// before taking the newly registered ones into account.
+const WAIT_TIMEOUT: u64 = 1_000_000;
+
+struct Entry {
+	fence: vk::Fence,
+	waker: Option<Waker>,
+	status: Option<Result<(), WaitError>>
+}
+
+#[derive(Default)]
+struct State {
+	entries: HashMap<u64, Entry>,
+	next_id: u64,
+	shutdown: bool,
+
+	/// Is the waiter thread blocked on the pending fences, without holding the lock.
+	waiting: bool,
+
+	/// Number of waits started by the waiter thread.
+	wait_count: u64
+}
+
+struct Shared {
+	device: Arc<Device>,
+	state: Mutex<State>,
+	condvar: Condvar,
+
+	/// Notified each time the waiter thread stops waiting on the pending fences.
+	idle: Condvar
+}
+
+impl Shared {
+	/// Background thread loop.
+	///
+	/// Waits for any of the pending fences and wakes up the tasks of the signaled ones.
+	fn run(&self) {
+		let device = self.device.handle();
+		let mut fences = Vec::new();
+
+		loop {
+			{
+				let mut state = self.state.lock().unwrap();
+				loop {
+					if state.shutdown {
+						for entry in state.entries.values_mut() {
+							if let Some(waker) = entry.waker.take() {
+								waker.wake()
+							}
+						}
+
+						return
+					}
+
+					fences.clear();
+					fences.extend(state.entries.values().filter(|e| e.status.is_none()).map(|e| e.fence));
+
+					if fences.is_empty() {
+						state = self.condvar.wait(state).unwrap()
+					} else {
+						state.waiting = true;
+						state.wait_count += 1;
+						break
+					}
+				}
+			}
+
+			let result = unsafe {
+				device.fp_v1_0().wait_for_fences(device.handle(), fences.len() as u32, fences.as_ptr(), vk::FALSE, WAIT_TIMEOUT)
+			};
+
+			let mut state = self.state.lock().unwrap();
+			state.waiting = false;
+			self.idle.notify_all();
+
+			for entry in state.entries.values_mut().filter(|e| e.status.is_none()) {
+				let status = match result {
+					vk::Result::SUCCESS | vk::Result::TIMEOUT => unsafe {
+						match device.fp_v1_0().get_fence_status(device.handle(), entry.fence) {
+							vk::Result::SUCCESS => Some(Ok(())),
+							vk::Result::NOT_READY => None,
+							e => Some(Err(e.into()))
+						}
+					},
+					e => Some(Err(e.into()))
+				};
+
+				if status.is_some() {
+					entry.status = status;
+					if let Some(waker) = entry.waker.take() {
+						waker.wake()
+					}
+				}
+			}
+		}
+	}
+}
+
+/// Fence waiter.
+///
+/// Owns a background thread waiting for the fences of the registered futures,
+/// so that they can be awaited in an asynchronous context without blocking the executor.
+pub struct Waiter {
+	shared: Arc<Shared>,
+	thread: Option<thread::JoinHandle<()>>
+}
+
+impl Waiter {
+	/// Spawns the waiter thread for fences of the given device.
+	pub fn new(device: &Arc<Device>) -> Waiter {
+		let shared = Arc::new(Shared {
+			device: device.clone(),
+			state: Mutex::new(State::default()),
+			condvar: Condvar::new(),
+			idle: Condvar::new()
+		});
+
+		let thread = {
+			let shared = shared.clone();
+			thread::Builder::new().name("fence waiter".to_string()).spawn(move || shared.run()).expect("unable to spawn fence waiter thread")
+		};
+
+		Waiter {
+			shared,
+			thread: Some(thread)
+		}
+	}
+
+	/// Turns the given fence future into a `std::future::Future`.
+	///
+	/// The returned future resolves to the payload of the fence future once the fence is signaled.
+	pub fn wait<F: WithPayload>(&self, future: F) -> AsyncWait<F> {
+		let mut state = self.shared.state.lock().unwrap();
+		let id = state.next_id;
+		state.next_id += 1;
+		state.entries.insert(id, Entry {
+			fence: *future.fence(),
+			waker: None,
+			status: None
+		});
+		self.shared.condvar.notify_one();
+
+		AsyncWait {
+			shared: self.shared.clone(),
+			id,
+			future: Some(future)
+		}
+	}
+}
+
+impl Drop for Waiter {
+	fn drop(&mut self) {
+		self.shared.state.lock().unwrap().shutdown = true;
+		self.shared.condvar.notify_one();
+
+		if let Some(thread) = self.thread.take() {
+			thread.join().ok();
+		}
+	}
+}
+
+/// Asynchronous fence wait.
+///
+/// Created with [`Waiter::wait`].
+#[must_use]
+pub struct AsyncWait<F> {
+	shared: Arc<Shared>,
+	id: u64,
+	future: Option<F>
+}
+
+// The fence future is never pinned.
+impl<F> Unpin for AsyncWait<F> {}
+
+impl<F: WithPayload> std::future::Future for AsyncWait<F> {
+	type Output = Result<F::Payload, WaitError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		let status = {
+			let mut state = self.shared.state.lock().unwrap();
+			let shutdown = state.shutdown;
+			let entry = state.entries.get_mut(&self.id).unwrap();
+			match entry.status.take() {
+				Some(status) => Some(status),
+				None if shutdown => None,
+				None => {
+					entry.waker = Some(cx.waker().clone());
+					return Poll::Pending
+				}
+			}
+		};
+
+		let future = self.future.take().expect("polled after completion");
+		let result = match status {
+			Some(Ok(())) => Ok(()),
+			Some(Err(e)) => Err(e),
+			None => unsafe {
+				// The waiter thread is gone, block on the fence.
+				self.shared.device.handle().wait_for_fences(std::slice::from_ref(future.fence()), true, u64::MAX).map_err(WaitError::from)
+			}
+		};
+
+		self.shared.state.lock().unwrap().entries.remove(&self.id);
+
+		Poll::Ready(result.and_then(|()| match future.unwrap_payload() {
+			Ok(payload) => Ok(payload),
+			Err(UnwrapError::DeviceLost) => Err(WaitError::DeviceLost),
+			Err(UnwrapError::Unsignaled(_)) => unreachable!()
+		}))
+	}
+}
+
+impl<F> Drop for AsyncWait<F> {
+	/// Unregisters the fence.
+	///
+	/// If the waiter thread is currently waiting on it,
+	/// this blocks until the wait returns (at most `WAIT_TIMEOUT`)
+	/// so that the fence is not destroyed while in use.
+	fn drop(&mut self) {
+		if let Ok(mut state) = self.shared.state.lock() {
+			if let Some(entry) = state.entries.remove(&self.id) {
+				if entry.status.is_none() {
+					let wait_count = state.wait_count;
+					while state.waiting && state.wait_count == wait_count {
+						state = match self.shared.idle.wait(state) {
+							Ok(state) => state,
+							Err(_) => return
+						}
+					}
+				}
+			}
+		}
+	}
+}
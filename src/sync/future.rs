@@ -8,6 +8,10 @@ use super::{
 	fence
 };
 
+mod join;
+
+pub use join::*;
+
 /// GPU future.
 pub unsafe trait Future {
 	/// Semaphore signaled when the future finishes.
@@ -15,11 +19,20 @@ pub unsafe trait Future {
 		None
 	}
 
+	/// Value signaled to the semaphore when the future finishes,
+	/// if it is a timeline semaphore.
+	fn signal_semaphore_value(&self) -> Option<&u64> {
+		None
+	}
+
 	/// Fence signaled when the future finishes.
 	fn signal_fence(&self) -> Option<&vk::Fence> {
 		None
 	}
 
+	/// Notify the future that a wait operation on its semaphore has been submitted.
+	fn wait_submitted(&self) {}
+
 	// fn uses(&self, resource: &dyn resource::AbstractReference) -> bool;
 }
 
@@ -31,11 +44,21 @@ pub unsafe trait Futures {
 	/// Otherwise, a fence is signaled when all the futures are done.
 	fn signal_semaphores(&self) -> Option<&[vk::Semaphore]>;
 
+	/// Values signaled to the semaphores, if some of them are timeline semaphores.
+	/// 
+	/// If not `None`, there is one value per semaphore (ignored for binary semaphores).
+	fn signal_semaphore_values(&self) -> Option<&[u64]> {
+		None
+	}
+
 	/// Fence signaled when *all* the futures are done.
 	/// 
 	/// If `None`, then each underlying future signals a semaphore.
 	fn signal_fence(&self) -> Option<&vk::Fence>;
 
+	/// Notify the futures that a wait operation on their semaphores has been submitted.
+	fn wait_submitted(&self) {}
+
 	// fn uses(&self, resource: &dyn resource::AbstractReference) -> bool;
 }
 
@@ -44,10 +67,18 @@ unsafe impl<F: Future> Futures for F {
 		self.signal_semaphore().map(std::slice::from_ref)
 	}
 
+	fn signal_semaphore_values(&self) -> Option<&[u64]> {
+		self.signal_semaphore_value().map(std::slice::from_ref)
+	}
+
 	fn signal_fence(&self) -> Option<&vk::Fence> {
 		Future::signal_fence(self)
 	}
 
+	fn wait_submitted(&self) {
+		Future::wait_submitted(self)
+	}
+
 	// fn uses(&self, resource: &dyn resource::AbstractReference) -> bool {
 	// 	Future::uses(self, resource)
 	// }
@@ -87,9 +118,33 @@ pub trait SignalFence: Futures {
 	}
 }
 
+/// Fence future giving back its task payload once signaled.
+pub trait WithPayload: SignalFence + Sized {
+	/// Payload of the future, released once the fence is signaled.
+	type Payload;
+
+	/// Unwrap the future and returns its payload.
+	/// 
+	/// The fence must be signaled.
+	fn unwrap_payload(self) -> Result<Self::Payload, fence::UnwrapError<Self>>;
+
+	/// Wait for the fence on the background thread of the given waiter,
+	/// without blocking the current thread.
+	#[inline]
+	fn wait_async(self, waiter: &fence::Waiter) -> fence::AsyncWait<Self> {
+		waiter.wait(self)
+	}
+}
+
 pub trait SignalSemaphores: Futures {
 	fn semaphores(&self) -> &[vk::Semaphore];
 
+	/// Values to wait for on each semaphore, if some of them are timeline semaphores.
+	#[inline]
+	fn semaphore_values(&self) -> Option<&[u64]> {
+		Futures::signal_semaphore_values(self)
+	}
+
 	#[inline]
 	fn and_then_pipeline_stages_of<T: task::WaitPipelineStages>(self, task: T, wait_pipeline_stage_mask: pipeline::stage::Flags) -> task::DelayedPipelineStages<Self, T> where Self: Sized {
 		// ...
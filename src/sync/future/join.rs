@@ -0,0 +1,157 @@
+use ash::vk;
+use crate::pipeline;
+use super::{
+	Futures,
+	SignalSemaphore,
+	SignalSemaphores,
+	task
+};
+
+/// Semaphores waited on by a join.
+#[derive(Default)]
+struct WaitSet {
+	semaphores: Vec<vk::Semaphore>,
+	values: Vec<u64>,
+	timeline: bool,
+	wait_pipeline_stage_masks: Vec<pipeline::stage::Flags>
+}
+
+impl WaitSet {
+	fn push<F: SignalSemaphore>(&mut self, future: &F, wait_pipeline_stage_mask: pipeline::stage::Flags) {
+		self.semaphores.push(*future.semaphore());
+		match future.signal_semaphore_value() {
+			Some(value) => {
+				self.values.push(*value);
+				self.timeline = true
+			},
+			None => self.values.push(0) // ignored for binary semaphores.
+		}
+		self.wait_pipeline_stage_masks.push(wait_pipeline_stage_mask)
+	}
+}
+
+/// Semaphore futures held by a join.
+pub trait Joined {
+	/// Notify the futures that a wait operation on their semaphores has been submitted.
+	fn wait_submitted(&self);
+}
+
+impl<F: SignalSemaphore> Joined for F {
+	fn wait_submitted(&self) {
+		super::Future::wait_submitted(self)
+	}
+}
+
+impl<F: Joined, G: SignalSemaphore> Joined for (F, G) {
+	fn wait_submitted(&self) {
+		self.0.wait_submitted();
+		super::Future::wait_submitted(&self.1)
+	}
+}
+
+impl<F: SignalSemaphore> Joined for Vec<F> {
+	fn wait_submitted(&self) {
+		for future in self {
+			super::Future::wait_submitted(future)
+		}
+	}
+}
+
+/// Group of semaphore futures waited on together.
+///
+/// Each semaphore is waited on at its own pipeline stages.
+#[must_use]
+pub struct Join<F> {
+	futures: F,
+	set: WaitSet
+}
+
+/// Join two semaphore futures, waited on at the given pipeline stages.
+pub fn join<A: SignalSemaphore, B: SignalSemaphore>(a: A, a_wait_pipeline_stage_mask: pipeline::stage::Flags, b: B, b_wait_pipeline_stage_mask: pipeline::stage::Flags) -> Join<(A, B)> {
+	Join::new(a, a_wait_pipeline_stage_mask).and(b, b_wait_pipeline_stage_mask)
+}
+
+/// Join any number of semaphore futures, each waited on at the given pipeline stages.
+pub fn join_all<F: SignalSemaphore, I: IntoIterator<Item = (F, pipeline::stage::Flags)>>(futures: I) -> Join<Vec<F>> {
+	let mut set = WaitSet::default();
+	let futures = futures.into_iter().map(|(future, wait_pipeline_stage_mask)| {
+		set.push(&future, wait_pipeline_stage_mask);
+		future
+	}).collect();
+
+	Join {
+		futures,
+		set
+	}
+}
+
+impl<F: SignalSemaphore> Join<F> {
+	/// Start a join with a single semaphore future, waited on at the given pipeline stages.
+	pub fn new(future: F, wait_pipeline_stage_mask: pipeline::stage::Flags) -> Self {
+		let mut set = WaitSet::default();
+		set.push(&future, wait_pipeline_stage_mask);
+
+		Join {
+			futures: future,
+			set
+		}
+	}
+}
+
+impl<F> Join<F> {
+	/// Add another semaphore future, waited on at the given pipeline stages.
+	pub fn and<G: SignalSemaphore>(self, future: G, wait_pipeline_stage_mask: pipeline::stage::Flags) -> Join<(F, G)> {
+		let mut set = self.set;
+		set.push(&future, wait_pipeline_stage_mask);
+
+		Join {
+			futures: (self.futures, future),
+			set
+		}
+	}
+
+	/// Pipeline stages at which each semaphore is waited on.
+	pub fn wait_pipeline_stage_masks(&self) -> &[pipeline::stage::Flags] {
+		&self.set.wait_pipeline_stage_masks
+	}
+
+	pub fn into_inner(self) -> F {
+		self.futures
+	}
+}
+
+impl<F: Joined> Join<F> {
+	/// Execute the given task once all the joined futures are signaled.
+	pub fn and_then<T: task::WaitPipelineStages>(self, task: T) -> task::DelayedPipelineStages<Self, T> {
+		let wait_pipeline_stage_masks = self.set.wait_pipeline_stage_masks.clone();
+		task::DelayedPipelineStages::with_masks(self, task, wait_pipeline_stage_masks)
+	}
+}
+
+unsafe impl<F: Joined> Futures for Join<F> {
+	fn signal_semaphores(&self) -> Option<&[vk::Semaphore]> {
+		Some(&self.set.semaphores)
+	}
+
+	fn signal_semaphore_values(&self) -> Option<&[u64]> {
+		if self.set.timeline {
+			Some(&self.set.values)
+		} else {
+			None
+		}
+	}
+
+	fn signal_fence(&self) -> Option<&vk::Fence> {
+		None
+	}
+
+	fn wait_submitted(&self) {
+		Joined::wait_submitted(&self.futures)
+	}
+}
+
+impl<F: Joined> SignalSemaphores for Join<F> {
+	fn semaphores(&self) -> &[vk::Semaphore] {
+		&self.set.semaphores
+	}
+}
@@ -6,9 +6,17 @@ pub mod future;
 pub mod semaphore;
 pub mod fence;
 pub mod sharing_mode;
+mod pool;
 
 pub use task::Task;
 pub use future::Future;
-pub use semaphore::Semaphore;
-pub use fence::Fence;
+pub use semaphore::{
+	Semaphore,
+	Timeline,
+	Pool as SemaphorePool
+};
+pub use fence::{
+	Fence,
+	Pool as FencePool
+};
 pub use sharing_mode::SharingQueues;
\ No newline at end of file
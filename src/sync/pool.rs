@@ -0,0 +1,68 @@
+use std::sync::{
+	Arc,
+	Mutex
+};
+
+/// Synchronization primitive that can be recycled by a pool.
+pub(crate) trait Recycle {
+	/// Prepare the object for reuse.
+	///
+	/// Returns `false` if the object cannot be safely reused,
+	/// in which case it is destroyed instead of returned to its pool.
+	fn recycle(&self) -> bool;
+}
+
+/// Free objects of a pool.
+pub(crate) struct Free<T> {
+	objects: Arc<Mutex<Vec<T>>>
+}
+
+impl<T: Recycle> Free<T> {
+	pub fn new() -> Self {
+		Free {
+			objects: Arc::new(Mutex::new(Vec::new()))
+		}
+	}
+
+	/// Number of free objects.
+	pub fn len(&self) -> usize {
+		self.objects.lock().unwrap().len()
+	}
+
+	/// Borrow a free object, or create a new one if there is none.
+	pub fn get<E>(&self, create: impl FnOnce() -> Result<T, E>) -> Result<Borrowed<T>, E> {
+		let object = match self.objects.lock().unwrap().pop() {
+			Some(object) => object,
+			None => create()?
+		};
+
+		Ok(Borrowed {
+			object: Some(object),
+			free: self.objects.clone()
+		})
+	}
+}
+
+/// Object borrowed from a pool.
+///
+/// Recycled and returned to its pool when dropped, or destroyed if it cannot be recycled.
+pub(crate) struct Borrowed<T: Recycle> {
+	object: Option<T>,
+	free: Arc<Mutex<Vec<T>>>
+}
+
+impl<T: Recycle> Borrowed<T> {
+	#[inline]
+	pub fn get(&self) -> &T {
+		self.object.as_ref().unwrap()
+	}
+}
+
+impl<T: Recycle> Drop for Borrowed<T> {
+	fn drop(&mut self) {
+		let object = self.object.take().unwrap();
+		if object.recycle() {
+			self.free.lock().unwrap().push(object)
+		}
+	}
+}
@@ -4,7 +4,13 @@ use ash::{
 };
 use std::{
 	borrow::Borrow,
-	sync::Arc
+	sync::{
+		Arc,
+		atomic::{
+			AtomicBool,
+			Ordering
+		}
+	}
 };
 use crate::{
 	OomError,
@@ -17,14 +23,27 @@ use super::{
 	future
 };
 
+pub mod timeline;
+mod pool;
+
+pub use timeline::Timeline;
+pub use pool::*;
+
 pub type VulkanSemaphore = vk::Semaphore;
 
 pub trait Semaphore {
 	fn handle(&self) -> &VulkanSemaphore;
 
+	/// Record that a signal operation on this semaphore has been submitted.
+	fn signal_submitted(&self) {}
+
+	/// Record that a wait operation on this semaphore has been submitted.
+	fn wait_submitted(&self) {}
+
 	/// Signal this semaphore after executing the given task.
 	fn signal<T: task::SignalSemaphore>(self, task: T) -> Result<(T::Output, Future<T::Payload, Self>), T::Error> where Self: Sized {
-		let (output, payload) = task.execute(Some(&[*self.handle()]), None)?;
+		let (output, payload) = task.execute(Some(&[*self.handle()]), None, None)?;
+		self.signal_submitted();
 
 		let future = Future {
 			payload,
@@ -52,6 +71,10 @@ unsafe impl<P: task::Payload, S: Semaphore> future::Future for Future<P, S> {
 		Some(self.semaphore.handle())
 	}
 
+	fn wait_submitted(&self) {
+		self.semaphore.wait_submitted()
+	}
+
 	// fn uses(&self, resource: &dyn resource::AbstractReference) -> bool {
 	// 	self.payload.uses(resource)
 	// }
@@ -76,7 +99,10 @@ impl From<vk::Result> for CreationError {
 
 pub struct Raw {
 	device: Arc<Device>,
-	handle: vk::Semaphore
+	handle: vk::Semaphore,
+
+	/// Has a signal operation been submitted, without any wait operation submitted since.
+	pending_signal: AtomicBool
 }
 
 impl Raw {
@@ -91,9 +117,16 @@ impl Raw {
 
 		Ok(Raw {
 			device: device.clone(),
-			handle
+			handle,
+			pending_signal: AtomicBool::new(false)
 		})
 	}
+
+	/// Checks if the semaphore is signaled, or about to be, without being waited on.
+	#[inline]
+	pub fn is_pending(&self) -> bool {
+		self.pending_signal.load(Ordering::Acquire)
+	}
 }
 
 impl Drop for Raw {
@@ -108,6 +141,14 @@ impl<T: Borrow<Raw>> Semaphore for T {
 	fn handle(&self) -> &vk::Semaphore {
 		&self.borrow().handle
 	}
+
+	fn signal_submitted(&self) {
+		self.borrow().pending_signal.store(true, Ordering::Release)
+	}
+
+	fn wait_submitted(&self) {
+		self.borrow().pending_signal.store(false, Ordering::Release)
+	}
 }
 
 impl DeviceOwned for Raw {
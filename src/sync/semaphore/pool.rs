@@ -0,0 +1,76 @@
+use std::{
+	borrow::Borrow,
+	sync::Arc
+};
+use crate::{
+	Device,
+	DeviceOwned,
+	sync::pool
+};
+use super::{
+	Raw,
+	CreationError
+};
+
+impl pool::Recycle for Raw {
+	/// A semaphore signaled but never waited on would still be signaled when reused,
+	/// so it is destroyed instead.
+	fn recycle(&self) -> bool {
+		!self.is_pending()
+	}
+}
+
+/// Binary semaphore pool.
+///
+/// Recycles the semaphores of the pool instead of destroying them.
+pub struct Pool {
+	device: Arc<Device>,
+	free: pool::Free<Raw>
+}
+
+impl Pool {
+	pub fn new(device: &Arc<Device>) -> Pool {
+		Pool {
+			device: device.clone(),
+			free: pool::Free::new()
+		}
+	}
+
+	/// Number of unused semaphores in the pool.
+	pub fn free_count(&self) -> usize {
+		self.free.len()
+	}
+
+	/// Get an unsignaled semaphore from the pool.
+	///
+	/// A new semaphore is created if the pool is empty.
+	pub fn get(&self) -> Result<Pooled, CreationError> {
+		Ok(Pooled(self.free.get(|| Raw::new(&self.device))?))
+	}
+}
+
+impl DeviceOwned for Pool {
+	fn device(&self) -> &Arc<Device> {
+		&self.device
+	}
+}
+
+/// Semaphore borrowed from a pool.
+///
+/// The semaphore is returned to its pool when dropped.
+/// Since its future is part of the payload of the task waiting on it,
+/// this happens once the consuming submission completes.
+/// If it has been signaled but no wait on it has been submitted, it is destroyed instead.
+pub struct Pooled(pool::Borrowed<Raw>);
+
+impl Borrow<Raw> for Pooled {
+	fn borrow(&self) -> &Raw {
+		self.0.get()
+	}
+}
+
+impl DeviceOwned for Pooled {
+	fn device(&self) -> &Arc<Device> {
+		self.0.get().device()
+	}
+}
@@ -0,0 +1,235 @@
+//! Timeline semaphores.
+//!
+//! Requires the `VK_KHR_timeline_semaphore` device extension.
+use ash::{
+	vk,
+	version::DeviceV1_0
+};
+use std::{
+	borrow::Borrow,
+	sync::Arc
+};
+use crate::{
+	OomError,
+	Device,
+	DeviceOwned,
+	device::MissingExtensionError,
+	sync::{
+		task,
+		future,
+		fence::WaitError
+	}
+};
+use super::VulkanSemaphore;
+
+pub trait Timeline: DeviceOwned {
+	fn handle(&self) -> &VulkanSemaphore;
+
+	/// Set the counter of this timeline to `value` after executing the given task.
+	fn signal_value<T: task::SignalTimeline>(self, value: u64, task: T) -> Result<(T::Output, Future<T::Payload, Self>), T::Error> where Self: Sized {
+		let (output, payload) = task.execute(Some(&[*self.handle()]), Some(&[value]), None)?;
+
+		let future = Future {
+			payload,
+			timeline: self,
+			value
+		};
+
+		Ok((output, future))
+	}
+
+	/// Future completed when the counter of this timeline reaches `value`.
+	///
+	/// The returned future can be waited on by the host, or by another task with `and_then`.
+	fn wait_value(self, value: u64) -> Future<(), Self> where Self: Sized {
+		Future {
+			payload: (),
+			timeline: self,
+			value
+		}
+	}
+
+	/// Current value of the timeline counter.
+	fn counter_value(&self) -> Result<u64, WaitError> {
+		let ext = self.device().ext_khr_timeline_semaphore().unwrap();
+		let mut value = 0;
+
+		unsafe {
+			match ext.get_semaphore_counter_value_khr(self.device().handle().handle(), *self.handle(), &mut value) {
+				vk::Result::SUCCESS => Ok(value),
+				e => Err(e.into())
+			}
+		}
+	}
+
+	/// Block until the counter of this timeline reaches `value`.
+	///
+	/// Returns `false` if the timeout expired first.
+	fn wait(&self, value: u64, timeout: Option<u64>) -> Result<bool, WaitError> {
+		let ext = self.device().ext_khr_timeline_semaphore().unwrap();
+
+		let infos = vk::SemaphoreWaitInfo {
+			semaphore_count: 1,
+			p_semaphores: self.handle(),
+			p_values: &value,
+			..Default::default()
+		};
+
+		unsafe {
+			match ext.wait_semaphores_khr(self.device().handle().handle(), &infos, timeout.unwrap_or(u64::MAX)) {
+				vk::Result::SUCCESS => Ok(true),
+				vk::Result::TIMEOUT => Ok(false),
+				e => Err(e.into())
+			}
+		}
+	}
+
+	/// Set the counter of this timeline to `value` from the host.
+	///
+	/// The value must be greater than the current value of the counter,
+	/// and than any value signaled by pending tasks.
+	fn signal(&self, value: u64) -> Result<(), OomError> {
+		let ext = self.device().ext_khr_timeline_semaphore().unwrap();
+
+		let infos = vk::SemaphoreSignalInfo {
+			semaphore: *self.handle(),
+			value,
+			..Default::default()
+		};
+
+		unsafe {
+			match ext.signal_semaphore_khr(self.device().handle().handle(), &infos) {
+				vk::Result::SUCCESS => Ok(()),
+				e => Err(e.into())
+			}
+		}
+	}
+}
+
+/// Timeline point.
+///
+/// Completed when the counter of the timeline reaches the value of the future.
+#[must_use]
+pub struct Future<P, T> {
+	payload: P,
+	timeline: T,
+	value: u64
+}
+
+impl<P, T> Future<P, T> {
+	pub fn payload(&self) -> &P {
+		&self.payload
+	}
+
+	pub fn timeline(&self) -> &T {
+		&self.timeline
+	}
+
+	pub fn value(&self) -> u64 {
+		self.value
+	}
+}
+
+impl<P, T: Timeline> Future<P, T> {
+	/// Block until the timeline reaches the value of the future.
+	///
+	/// Returns `false` if the timeout expired first.
+	pub fn wait(&self, timeout: Option<u64>) -> Result<bool, WaitError> {
+		self.timeline.wait(self.value, timeout)
+	}
+
+	pub fn is_signaled(&self) -> Result<bool, WaitError> {
+		Ok(self.timeline.counter_value()? >= self.value)
+	}
+
+	/// Block until the timeline reaches the value of the future, and returns the payload of the future.
+	pub fn wait_payload(self) -> Result<P, WaitError> {
+		self.timeline.wait(self.value, None)?;
+		Ok(self.payload)
+	}
+}
+
+unsafe impl<P: task::Payload, T: Timeline> future::Future for Future<P, T> {
+	fn signal_semaphore(&self) -> Option<&vk::Semaphore> {
+		Some(self.timeline.handle())
+	}
+
+	fn signal_semaphore_value(&self) -> Option<&u64> {
+		Some(&self.value)
+	}
+}
+
+impl<P: task::Payload, T: Timeline> future::SignalSemaphore for Future<P, T> {}
+
+#[derive(Debug)]
+pub enum CreationError {
+	OutOfMemory(OomError),
+	MissingExtension(MissingExtensionError)
+}
+
+impl From<vk::Result> for CreationError {
+	fn from(r: vk::Result) -> CreationError {
+		match r {
+			vk::Result::ERROR_OUT_OF_HOST_MEMORY => CreationError::OutOfMemory(OomError::Host),
+			vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => CreationError::OutOfMemory(OomError::Device),
+			_ => unreachable!()
+		}
+	}
+}
+
+impl From<MissingExtensionError> for CreationError {
+	fn from(e: MissingExtensionError) -> CreationError {
+		CreationError::MissingExtension(e)
+	}
+}
+
+pub struct Raw {
+	device: Arc<Device>,
+	handle: vk::Semaphore
+}
+
+impl Raw {
+	pub fn new(device: &Arc<Device>, initial_value: u64) -> Result<Raw, CreationError> {
+		device.ext_khr_timeline_semaphore()?;
+
+		let type_infos = vk::SemaphoreTypeCreateInfo {
+			semaphore_type: vk::SemaphoreType::TIMELINE,
+			initial_value,
+			..Default::default()
+		};
+
+		let infos = vk::SemaphoreCreateInfo {
+			p_next: &type_infos as *const _ as *const std::ffi::c_void,
+			..Default::default()
+		};
+
+		let handle = unsafe {
+			device.handle().create_semaphore(&infos, None)?
+		};
+
+		Ok(Raw {
+			device: device.clone(),
+			handle
+		})
+	}
+}
+
+impl Drop for Raw {
+	fn drop(&mut self) {
+		unsafe {
+			self.device.handle().destroy_semaphore(self.handle, None)
+		}
+	}
+}
+
+impl<T: Borrow<Raw> + DeviceOwned> Timeline for T {
+	fn handle(&self) -> &VulkanSemaphore {
+		&self.borrow().handle
+	}
+}
+
+impl DeviceOwned for Raw {
+	fn device(&self) -> &Arc<Device> {
+		&self.device
+	}
+}
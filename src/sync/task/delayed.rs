@@ -16,7 +16,7 @@ impl<P: future::SignalSemaphore, T: Wait> Delayed<P, T> {
 impl<P: future::SignalSemaphore, T: Wait<Payload=()>> Delayed<P, T> {
 	/// Execute the task without signaling any semaphore or fence upon completion.
 	pub fn in_parallel(self) -> Result<(T::Output, P), T::Error> {
-		let (output, (past, ())) = self.execute(None, None)?;
+		let (output, (past, ())) = self.execute(None, None, None)?;
 		Ok((output, past))
 	}
 }
@@ -30,26 +30,39 @@ unsafe impl<P: future::SignalSemaphore, T: Wait> Task for Delayed<P, T> {
 	fn execute(
 		self,
 		signal_semaphore: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<(Self::Output, Self::Payload), Self::Error> {
-		let (output, payload) = self.task.execute(Some(&self.past), signal_semaphore, signal_fence)?;
+		let (output, payload) = self.task.execute(Some(&self.past), signal_semaphore, signal_semaphore_values, signal_fence)?;
+		future::Futures::wait_submitted(&self.past);
 		Ok((output, (self.past, payload)))
 	}
 }
 
 impl<P: future::SignalSemaphore, T: Wait + SignalSemaphore> SignalSemaphore for Delayed<P, T> {}
+impl<P: future::SignalSemaphore, T: Wait + SignalTimeline> SignalTimeline for Delayed<P, T> {}
 impl<P: future::SignalSemaphore, T: Wait + SignalFence> SignalFence for Delayed<P, T> {}
 
 pub struct DelayedPipelineStages<P, T> {
 	past: P,
 	task: T,
-	wait_pipeline_stage_mask: pipeline::stage::Flags
+	wait_pipeline_stage_masks: Vec<pipeline::stage::Flags>
 }
 
 impl<P: future::SignalSemaphores, T: WaitPipelineStages> DelayedPipelineStages<P, T> {
+	/// Wait on every semaphore of `past` at the same pipeline stages.
 	pub fn new(past: P, task: T, wait_pipeline_stage_mask: pipeline::stage::Flags) -> Self {
+		let wait_pipeline_stage_masks = vec![wait_pipeline_stage_mask; past.semaphores().len()];
+		Self::with_masks(past, task, wait_pipeline_stage_masks)
+	}
+
+	/// Wait on each semaphore of `past` at its own pipeline stages.
+	///
+	/// There must be one mask per semaphore.
+	pub fn with_masks(past: P, task: T, wait_pipeline_stage_masks: Vec<pipeline::stage::Flags>) -> Self {
+		assert_eq!(wait_pipeline_stage_masks.len(), past.semaphores().len());
 		DelayedPipelineStages {
-			past, task, wait_pipeline_stage_mask
+			past, task, wait_pipeline_stage_masks
 		}
 	}
 }
@@ -63,12 +76,15 @@ unsafe impl<P: future::SignalSemaphores, T: WaitPipelineStages> Task for Delayed
 	fn execute(
 		self,
 		signal_semaphore: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<(Self::Output, Self::Payload), Self::Error> {
-		let (output, payload) = self.task.execute(Some(&self.past), Some(std::slice::from_ref(&self.wait_pipeline_stage_mask)), signal_semaphore, signal_fence)?;
+		let (output, payload) = self.task.execute(Some(&self.past), Some(&self.wait_pipeline_stage_masks), signal_semaphore, signal_semaphore_values, signal_fence)?;
+		future::Futures::wait_submitted(&self.past);
 		Ok((output, (self.past, payload)))
 	}
 }
 
 impl<P: future::SignalSemaphores, T: WaitPipelineStages + SignalSemaphore> SignalSemaphore for DelayedPipelineStages<P, T> {}
+impl<P: future::SignalSemaphores, T: WaitPipelineStages + SignalTimeline> SignalTimeline for DelayedPipelineStages<P, T> {}
 impl<P: future::SignalSemaphores, T: WaitPipelineStages + SignalFence> SignalFence for DelayedPipelineStages<P, T> {}
\ No newline at end of file
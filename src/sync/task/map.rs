@@ -18,11 +18,12 @@ unsafe impl<T: Task, U, F> Task for Map<T, F> where F: FnOnce(T::Output) -> U {
 	type Error = T::Error;
 	type Payload = T::Payload;
 
-	fn execute(self, signal_semaphore: Option<&[vk::Semaphore]>, signal_fence: Option<vk::Fence>) -> Result<(U, Self::Payload), Self::Error> {
-		let (output, payload) = self.task.execute(signal_semaphore, signal_fence)?;
+	fn execute(self, signal_semaphore: Option<&[vk::Semaphore]>, signal_semaphore_values: Option<&[u64]>, signal_fence: Option<vk::Fence>) -> Result<(U, Self::Payload), Self::Error> {
+		let (output, payload) = self.task.execute(signal_semaphore, signal_semaphore_values, signal_fence)?;
 		Ok(((self.f)(output), payload))
 	}
 }
 
 impl<T: SignalSemaphore, U, F> SignalSemaphore for Map<T, F> where F: FnOnce(T::Output) -> U {} 
+impl<T: SignalTimeline, U, F> SignalTimeline for Map<T, F> where F: FnOnce(T::Output) -> U {}
 impl<T: SignalFence, U, F> SignalFence for Map<T, F> where F: FnOnce(T::Output) -> U {} 
\ No newline at end of file
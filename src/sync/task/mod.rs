@@ -7,6 +7,10 @@ use super::{
 	future,
 	Semaphore,
 	semaphore,
+	semaphore::{
+		Timeline,
+		timeline
+	},
 	Fence,
 	fence
 };
@@ -79,9 +83,13 @@ pub unsafe trait Task: Sized {
 	/// Execute the task.
 	/// 
 	/// Note that `signal_semaphore` and `signal_fence` may not be used.
+	/// If not `None`, `signal_semaphore_values` holds the value signaled to each timeline semaphore
+	/// of `signal_semaphore` (ignored for binary semaphores), and must only be given to tasks
+	/// implementing [`SignalTimeline`].
 	fn execute(
 		self,
 		signal_semaphore: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<(Self::Output, Self::Payload), Self::Error>;
 
@@ -89,6 +97,10 @@ pub unsafe trait Task: Sized {
 		semaphore.signal(self)
 	}
 
+	fn then_signal_timeline<S: Timeline>(self, timeline: S, value: u64) -> Result<(Self::Output, timeline::Future<Self::Payload, S>), Self::Error> where Self: SignalTimeline {
+		timeline.signal_value(value, self)
+	}
+
 	fn then_signal_fence<F: Fence>(self, fence: F) -> Result<(Self::Output, fence::Future<Self::Payload, F>), Self::Error> where Self: SignalFence {
 		fence.signal(self)
 	}
@@ -116,10 +128,14 @@ pub unsafe trait Wait: Sized {
 	/// Execute the task after the given future (if any) has signaled its semaphores.
 	/// 
 	/// Note that `signal_semaphore` and `signal_fence` may not be used.
+	/// If not `None`, `signal_semaphore_values` holds the value signaled to each timeline semaphore
+	/// of `signal_semaphore` (ignored for binary semaphores), and must only be given to tasks
+	/// implementing [`SignalTimeline`].
 	fn execute<P: future::SignalSemaphores>(
 		self,
 		past: Option<&P>,
 		signal_semaphore: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<(Self::Output, Self::Payload), Self::Error>;
 }
@@ -133,9 +149,10 @@ unsafe impl<T: Wait> Task for T {
 	fn execute(
 		self,
 		signal_semaphore: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<(Self::Output, Self::Payload), Self::Error> {
-		Wait::execute::<semaphore::Future<(), semaphore::Raw>>(self, None, signal_semaphore, signal_fence)
+		Wait::execute::<semaphore::Future<(), semaphore::Raw>>(self, None, signal_semaphore, signal_semaphore_values, signal_fence)
 	}
 }
 
@@ -152,11 +169,15 @@ pub unsafe trait WaitPipelineStages: Sized {
 	/// Execute the task.
 	/// 
 	/// Note that `signal_semaphore` and `signal_fence` may not be used.
+	/// If not `None`, `signal_semaphore_values` holds the value signaled to each timeline semaphore
+	/// of `signal_semaphore` (ignored for binary semaphores), and must only be given to tasks
+	/// implementing [`SignalTimeline`].
 	fn execute<P: future::SignalSemaphores>(
 		self,
 		past: Option<&P>,
 		wait_pipeline_stage_mask: Option<&[pipeline::stage::Flags]>,
 		signal_semaphore: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<(Self::Output, Self::Payload), Self::Error>;
 }
@@ -171,14 +192,18 @@ unsafe impl<T: WaitPipelineStages> Wait for T {
 		self,
 		past: Option<&P>,
 		signal_semaphore: Option<&[vk::Semaphore]>,
+		signal_semaphore_values: Option<&[u64]>,
 		signal_fence: Option<vk::Fence>,
 	) -> Result<(Self::Output, Self::Payload), Self::Error> {
-		WaitPipelineStages::execute(self, past, None, signal_semaphore, signal_fence)
+		WaitPipelineStages::execute(self, past, None, signal_semaphore, signal_semaphore_values, signal_fence)
 	}
 }
 
 /// A task that *can* signal a semaphore upon completion.
 pub trait SignalSemaphore: Task {}
 
+/// A task that *can* signal a timeline semaphore upon completion.
+pub trait SignalTimeline: SignalSemaphore {}
+
 /// A task that *can* signal a fence upon completion.
 pub trait SignalFence: Task {}
\ No newline at end of file